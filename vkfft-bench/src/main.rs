@@ -0,0 +1,465 @@
+//! Throughput benchmark over the size matrix of VkFFT_benchmark.
+//!
+//! Every case runs a forward and an inverse C2C transform per iteration, timed on the GPU with
+//! timestamp queries around all iterations. Like VkFFT_benchmark, 1D sizes are batched up to a fixed
+//! number of elements, while 2D and 3D cases run a single system.
+//!
+//! ```text
+//! vkfft-bench [--device N] [--precision single,double,half] [--dims 1,2,3] [--elements N]
+//!             [--iterations N] [--runs N] [--json PATH]
+//! ```
+//!
+//! `--elements` caps the complex elements per buffer, 2^27 by default. Something like
+//! `--elements 65536 --iterations 2` keeps a run on lavapipe short.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use vkfft_rs::{
+    app::{App, LaunchParams},
+    command_buffer::FftCommandBufferBuilderExt,
+    config::{Config, Precision},
+    profile,
+};
+use vulkano::{
+    buffer::{BufferAccess, BufferUsage, DeviceLocalBuffer},
+    command_buffer::{
+        pool::{UnsafeCommandPool, UnsafeCommandPoolCreateInfo},
+        AutoCommandBufferBuilder, CommandBufferUsage, PrimaryCommandBuffer,
+    },
+    device::{physical::PhysicalDevice, Device, DeviceCreateInfo, Features, Queue, QueueCreateInfo},
+    instance::{Instance, InstanceCreateInfo},
+    query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType},
+    sync::{GpuFuture, PipelineStage},
+    VulkanLibrary,
+};
+
+/// The 2D and 3D systems of VkFFT_benchmark's 3D sample.
+const SYSTEMS: &[[u64; 3]] = &[
+    [1024, 1024, 1],
+    [720, 480, 1],
+    [1280, 720, 1],
+    [1920, 1080, 1],
+    [2560, 1440, 1],
+    [3840, 2160, 1],
+    [7680, 4320, 1],
+    [64, 64, 1],
+    [128, 64, 1],
+    [128, 128, 1],
+    [256, 128, 1],
+    [256, 256, 1],
+    [512, 256, 1],
+    [512, 512, 1],
+    [1024, 512, 1],
+    [2048, 1024, 1],
+    [2048, 2048, 1],
+    [4096, 2048, 1],
+    [4096, 4096, 1],
+    [8192, 4096, 1],
+    [8192, 8192, 1],
+    [16384, 8192, 1],
+    [16, 16, 16],
+    [32, 16, 16],
+    [32, 32, 16],
+    [32, 32, 32],
+    [64, 32, 32],
+    [64, 64, 32],
+    [64, 64, 64],
+    [128, 64, 64],
+    [128, 128, 64],
+    [128, 128, 128],
+    [256, 128, 128],
+    [256, 256, 128],
+    [256, 256, 256],
+    [512, 256, 256],
+    [512, 512, 256],
+    [512, 512, 512],
+];
+
+struct Args {
+    device: usize,
+    precisions: Vec<Precision>,
+    dims: Vec<u64>,
+    elements: u64,
+    iterations: Option<u64>,
+    runs: u64,
+    json: Option<String>,
+}
+
+fn parse_precision(name: &str) -> Precision {
+    match name {
+        "single" => Precision::Single,
+        "double" => Precision::Double,
+        "half" => Precision::Half,
+        _ => panic!("unknown precision {}", name),
+    }
+}
+
+fn precision_name(precision: Precision) -> &'static str {
+    match precision {
+        Precision::Single => "single",
+        Precision::Double => "double",
+        Precision::Half => "half",
+        Precision::HalfMemory => "half-memory",
+    }
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        device: 0,
+        precisions: vec![Precision::Single, Precision::Double, Precision::Half],
+        dims: vec![1, 2, 3],
+        elements: 1 << 27,
+        iterations: None,
+        runs: 3,
+        json: None,
+    };
+
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().unwrap_or_else(|| panic!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--device" => args.device = value().parse().expect("device index"),
+            "--precision" => args.precisions = value().split(',').map(parse_precision).collect(),
+            "--dims" => args.dims = value().split(',').map(|d| d.parse().expect("dimension")).collect(),
+            "--elements" => args.elements = value().parse().expect("element count"),
+            "--iterations" => args.iterations = Some(value().parse().expect("iteration count")),
+            "--runs" => args.runs = value().parse().expect("run count"),
+            "--json" => args.json = Some(value()),
+            _ => panic!("unknown argument {}", arg),
+        }
+    }
+
+    args
+}
+
+struct Case {
+    fft_dim: u64,
+    size: [u64; 3],
+    batches: u64,
+}
+
+/// The benchmark matrix, without cases whose buffer exceeds `elements`.
+fn cases(dims: &[u64], elements: u64) -> Vec<Case> {
+    let mut res = Vec::new();
+
+    if dims.contains(&1) {
+        res.extend((2..=27).map(|e| 1 << e).filter(|&x| x <= elements).map(|x| Case {
+            fft_dim: 1,
+            size: [x, 1, 1],
+            batches: elements / x,
+        }));
+    }
+
+    for &size in SYSTEMS {
+        let fft_dim = if size[2] > 1 { 3 } else { 2 };
+
+        if dims.contains(&fft_dim) && size.iter().product::<u64>() <= elements {
+            res.push(Case { fft_dim, size, batches: 1 });
+        }
+    }
+
+    res
+}
+
+#[derive(Serialize)]
+struct Record {
+    fft_dim: u64,
+    size: [u64; 3],
+    batches: u64,
+    precision: &'static str,
+    buffer_bytes: u64,
+    iterations: u64,
+    /// Average time of one forward and one inverse transform
+    time_ms: f64,
+    gb_per_s: f64,
+    gflop_per_s: f64,
+}
+
+#[derive(Serialize)]
+struct Report {
+    device: String,
+    driver_version: u32,
+    driver_info: Option<String>,
+    vkfft_version: String,
+    results: Vec<Record>,
+}
+
+struct Bench {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    timestamp_period: f64,
+    timestamp_valid_bits: u32,
+}
+
+impl Bench {
+    fn new(physical: Arc<PhysicalDevice>, precisions: &[Precision]) -> Self {
+        let queue_family_index = physical
+            .queue_family_properties()
+            .iter()
+            .position(|q| q.queue_flags.compute && q.timestamp_valid_bits.is_some())
+            .expect("a compute queue with timestamps") as u32;
+
+        let supported = physical.supported_features();
+        let double = precisions.contains(&Precision::Double) && supported.shader_float64;
+        let half = precisions.contains(&Precision::Half)
+            && supported.shader_float16
+            && supported.storage_buffer16_bit_access;
+
+        let timestamp_period = physical.properties().timestamp_period as f64;
+        let timestamp_valid_bits = physical.queue_family_properties()[queue_family_index as usize]
+            .timestamp_valid_bits
+            .expect("checked above");
+
+        let (device, mut queues) = Device::new(
+            physical,
+            DeviceCreateInfo {
+                enabled_features: Features {
+                    shader_float64: double,
+                    shader_float16: half,
+                    storage_buffer16_bit_access: half,
+                    ..Features::empty()
+                },
+                queue_create_infos: vec![QueueCreateInfo {
+                    queue_family_index,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        )
+        .expect("device");
+
+        Self {
+            device,
+            queue: queues.next().expect("queue"),
+            timestamp_period,
+            timestamp_valid_bits,
+        }
+    }
+
+    fn supports(&self, precision: Precision) -> bool {
+        let features = self.device.enabled_features();
+
+        match precision {
+            Precision::Double => features.shader_float64,
+            Precision::Half | Precision::HalfMemory => features.shader_float16 && features.storage_buffer16_bit_access,
+            Precision::Single => true,
+        }
+    }
+
+    fn app(&self, case: &Case, precision: Precision) -> (std::pin::Pin<Box<App>>, Arc<DeviceLocalBuffer<[u8]>>) {
+        let scalar = match precision {
+            Precision::Double => 8,
+            Precision::Half => 2,
+            _ => 4,
+        };
+
+        let bytes = case.size.iter().product::<u64>() * case.batches * 2 * scalar;
+
+        let buffer = DeviceLocalBuffer::<[u8]>::array(
+            self.device.clone(),
+            bytes,
+            BufferUsage {
+                storage_buffer: true,
+                ..BufferUsage::empty()
+            },
+            [self.queue.queue_family_index()],
+        )
+        .expect("buffer");
+
+        let command_pool = UnsafeCommandPool::new(
+            self.device.clone(),
+            UnsafeCommandPoolCreateInfo {
+                queue_family_index: self.queue.queue_family_index(),
+                reset_command_buffer: true,
+                ..Default::default()
+            },
+        )
+        .expect("command pool");
+
+        // The pool is only used through the `App` owning it.
+        #[allow(clippy::arc_with_non_send_sync)]
+        let command_pool = Arc::new(command_pool);
+
+        let builder = Config::builder()
+            .physical_device(self.device.physical_device().clone())
+            .device(self.device.clone())
+            .queue(self.queue.clone())
+            .command_pool(command_pool)
+            .buffer(buffer.clone())
+            .precision(precision)
+            .batch_count(case.batches);
+
+        let builder = match case.fft_dim {
+            1 => builder.dim(&[case.size[0]]),
+            2 => builder.dim(&[case.size[0], case.size[1]]),
+            _ => builder.dim(&case.size),
+        };
+
+        let app = App::new(builder.build().expect("config")).expect("app");
+        (app, buffer)
+    }
+
+    /// GPU time of `iterations` forward and inverse transforms, in milliseconds.
+    fn time(&self, app: &mut App, iterations: u64) -> f64 {
+        let pool = QueryPool::new(
+            self.device.clone(),
+            QueryPoolCreateInfo {
+                query_count: 2,
+                ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+            },
+        )
+        .expect("query pool");
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.device.clone(),
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .expect("command buffer");
+
+        unsafe {
+            builder
+                .reset_query_pool(pool.clone(), 0..2)
+                .expect("reset queries")
+                .write_timestamp(pool.clone(), 0, PipelineStage::TopOfPipe)
+                .expect("timestamp");
+        }
+
+        for _ in 0..iterations {
+            builder
+                .fft_forward(app, LaunchParams::builder())
+                .expect("forward")
+                .fft_inverse(app, LaunchParams::builder())
+                .expect("inverse");
+        }
+
+        unsafe {
+            builder
+                .write_timestamp(pool.clone(), 1, PipelineStage::BottomOfPipe)
+                .expect("timestamp");
+        }
+
+        builder
+            .build()
+            .expect("command buffer")
+            .execute(self.queue.clone())
+            .expect("submit")
+            .then_signal_fence_and_flush()
+            .expect("flush")
+            .wait(None)
+            .expect("wait");
+
+        let mut timestamps = [0u64; 2];
+        pool.queries_range(0..2)
+            .expect("queries")
+            .get_results(
+                &mut timestamps,
+                QueryResultFlags {
+                    wait: true,
+                    ..QueryResultFlags::empty()
+                },
+            )
+            .expect("timestamps");
+
+        profile::ticks_between(timestamps[0], timestamps[1], self.timestamp_valid_bits) as f64 * self.timestamp_period / 1e6
+    }
+
+    fn run(&self, case: &Case, precision: Precision, iterations: Option<u64>, runs: u64) -> Record {
+        let (mut app, buffer) = self.app(case, precision);
+        let buffer_bytes = buffer.size();
+
+        // Like VkFFT_benchmark, about 12 GiB of buffer traffic per run, at most 1000 iterations.
+        let iterations = iterations
+            .unwrap_or_else(|| (3 * 4096 * 1024 * 1024 / buffer_bytes).min(1000))
+            .max(1);
+
+        // An untimed run to warm up the device.
+        self.time(&mut app, 1);
+
+        let total: f64 = (0..runs).map(|_| self.time(&mut app, iterations)).sum();
+        let time_ms = total / (runs * iterations) as f64;
+
+        let n = case.size.iter().product::<u64>() as f64;
+        let flops = 2.0 * 5.0 * n * n.log2() * case.batches as f64;
+
+        Record {
+            fft_dim: case.fft_dim,
+            size: case.size,
+            batches: case.batches,
+            precision: precision_name(precision),
+            buffer_bytes,
+            iterations,
+            time_ms,
+            // Each transform reads and writes the whole buffer at least once.
+            gb_per_s: 4.0 * buffer_bytes as f64 / time_ms / 1e6,
+            gflop_per_s: flops / time_ms / 1e6,
+        }
+    }
+}
+
+fn main() {
+    let args = parse_args();
+
+    let library = VulkanLibrary::new().expect("Vulkan library");
+    let instance = Instance::new(
+        library,
+        InstanceCreateInfo {
+            enumerate_portability: true,
+            ..Default::default()
+        },
+    )
+    .expect("instance");
+
+    let physical = instance
+        .enumerate_physical_devices()
+        .expect("physical devices")
+        .nth(args.device)
+        .expect("no device with that index");
+
+    let properties = physical.properties();
+    let mut report = Report {
+        device: properties.device_name.clone(),
+        driver_version: properties.driver_version,
+        driver_info: properties.driver_info.clone(),
+        vkfft_version: vkfft_rs::version().to_string(),
+        results: Vec::new(),
+    };
+
+    eprintln!("device: {}, VkFFT {}", report.device, report.vkfft_version);
+
+    let bench = Bench::new(physical, &args.precisions);
+
+    for &precision in &args.precisions {
+        if !bench.supports(precision) {
+            eprintln!("skipping {} precision, which the device doesn't support", precision_name(precision));
+            continue;
+        }
+
+        for case in cases(&args.dims, args.elements) {
+            let record = bench.run(&case, precision, args.iterations, args.runs);
+
+            println!(
+                "{}D {}x{}x{} batch {} {}: {:.3} ms, {:.1} GB/s, {:.1} GFLOP/s",
+                record.fft_dim,
+                record.size[0],
+                record.size[1],
+                record.size[2],
+                record.batches,
+                record.precision,
+                record.time_ms,
+                record.gb_per_s,
+                record.gflop_per_s
+            );
+
+            report.results.push(record);
+        }
+    }
+
+    if let Some(path) = args.json {
+        let json = serde_json::to_string_pretty(&report).expect("serializing results");
+        std::fs::write(path, json).expect("writing results");
+    }
+}
@@ -0,0 +1,224 @@
+//! Measures the accuracy of forward C2C transforms against the CPU reference, like the precision
+//! samples of VkFFT do against FFTW.
+//!
+//! Sizes are swept in three groups: powers of two, primes VkFFT handles with Rader's algorithm and
+//! sizes with large prime factors that need Bluestein's algorithm. VkFFT picks the algorithm itself,
+//! so the groups only describe what the sizes are meant to exercise.
+//!
+//! ```text
+//! cargo run --release --example accuracy --features reference -- [--device N] [--max N] [--json PATH]
+//! ```
+//!
+//! Prints one CSV row per size and precision, and writes the same rows as JSON if asked to.
+
+use vkfft_rs::{
+    reference::{signal, Reference, Scalar},
+    Context,
+};
+use vulkano::{
+    device::{Device, DeviceCreateInfo, Features, QueueCreateInfo},
+    instance::{Instance, InstanceCreateInfo},
+    VulkanLibrary,
+};
+
+struct Row {
+    group: &'static str,
+    size: u64,
+    precision: &'static str,
+    max_error: f64,
+    l2_error: f64,
+}
+
+struct Args {
+    device: usize,
+    max: u64,
+    json: Option<String>,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        device: 0,
+        max: 1 << 20,
+        json: None,
+    };
+
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().unwrap_or_else(|| panic!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--device" => args.device = value().parse().expect("device index"),
+            "--max" => args.max = value().parse().expect("maximum size"),
+            "--json" => args.json = Some(value()),
+            _ => panic!("unknown argument {}", arg),
+        }
+    }
+
+    args
+}
+
+fn is_prime(n: u64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+fn sizes(max: u64) -> Vec<(&'static str, u64)> {
+    let powers = (1..).map(|e| 1 << e).take_while(|&n| n <= max);
+
+    // Primes up to a few thousand, where VkFFT uses Rader's algorithm.
+    let rader = [17, 31, 61, 127, 251, 509, 1021, 2039, 4093]
+        .into_iter()
+        .chain((5..4096).filter(|&n| is_prime(n)).step_by(64))
+        .filter(|&n| n <= max);
+
+    // Large primes and multiples of them, beyond what Rader's algorithm covers.
+    let bluestein = [8191, 3 * 8191, 65537, 2 * 65537, 131071, 524287]
+        .into_iter()
+        .filter(|&n| n <= max);
+
+    let mut res: Vec<_> = powers
+        .map(|n| ("pow2", n))
+        .chain(rader.map(|n| ("rader", n)))
+        .chain(bluestein.map(|n| ("bluestein", n)))
+        .collect();
+
+    res.sort_by_key(|&(group, n)| (n, group));
+    res.dedup();
+    res
+}
+
+fn measure<T: Scalar>(ctx: &Context, group: &'static str, size: u64) -> Row {
+    // The reference transforms the input as the device sees it, so only the transform's own error
+    // is measured.
+    let input: Vec<[T; 2]> = signal(2 * size as usize, size)
+        .chunks(2)
+        .map(|c| [T::from_f64(c[0]), T::from_f64(c[1])])
+        .collect();
+    let mut expected: Vec<[f64; 2]> = input.iter().map(|c| c.map(T::to_f64)).collect();
+    Reference::new(&[size as usize]).fft(&mut expected, false);
+
+    let mut output = input;
+    vkfft_rs::fft(ctx, &mut output, &[size]).expect("transform");
+
+    let mut max_diff = 0f64;
+    let mut max_norm = 0f64;
+    let mut diff2 = 0.0;
+    let mut norm2 = 0.0;
+
+    for (actual, expected) in output.iter().zip(&expected) {
+        let actual = actual.map(T::to_f64);
+        let d = (actual[0] - expected[0]).hypot(actual[1] - expected[1]);
+        let n = expected[0].hypot(expected[1]);
+
+        max_diff = max_diff.max(d);
+        max_norm = max_norm.max(n);
+        diff2 += d * d;
+        norm2 += n * n;
+    }
+
+    Row {
+        group,
+        size,
+        precision: T::NAME,
+        max_error: max_diff / max_norm,
+        l2_error: (diff2 / norm2).sqrt(),
+    }
+}
+
+fn to_json(rows: &[Row]) -> String {
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|r| {
+            format!(
+                "  {{\"group\": \"{}\", \"size\": {}, \"precision\": \"{}\", \"max_error\": {:e}, \"l2_error\": {:e}}}",
+                r.group, r.size, r.precision, r.max_error, r.l2_error
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\n\"vkfft_version\": \"{}\",\n\"results\": [\n{}\n]\n}}\n",
+        vkfft_rs::version(),
+        rows.join(",\n")
+    )
+}
+
+fn main() {
+    let args = parse_args();
+
+    let library = VulkanLibrary::new().expect("Vulkan library");
+    let instance = Instance::new(
+        library,
+        InstanceCreateInfo {
+            enumerate_portability: true,
+            ..Default::default()
+        },
+    )
+    .expect("instance");
+
+    let physical = instance
+        .enumerate_physical_devices()
+        .expect("physical devices")
+        .nth(args.device)
+        .expect("no device with that index");
+
+    let queue_family_index = physical
+        .queue_family_properties()
+        .iter()
+        .position(|q| q.queue_flags.compute)
+        .expect("compute queue") as u32;
+
+    let supported = physical.supported_features();
+    let double = supported.shader_float64;
+    let half = cfg!(feature = "half") && supported.shader_float16 && supported.storage_buffer16_bit_access;
+
+    eprintln!("device: {}", physical.properties().device_name);
+
+    let (device, mut queues) = Device::new(
+        physical,
+        DeviceCreateInfo {
+            enabled_features: Features {
+                shader_float64: double,
+                shader_float16: half,
+                storage_buffer16_bit_access: half,
+                ..Features::empty()
+            },
+            queue_create_infos: vec![QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    )
+    .expect("device");
+
+    let ctx = Context::new(device, queues.next().expect("queue"));
+
+    let mut rows = Vec::new();
+    println!("group,size,precision,max_error,l2_error");
+
+    for (group, size) in sizes(args.max) {
+        let mut run = |row: Row| {
+            println!("{},{},{},{:e},{:e}", row.group, row.size, row.precision, row.max_error, row.l2_error);
+            rows.push(row);
+        };
+
+        run(measure::<f32>(&ctx, group, size));
+
+        if double {
+            run(measure::<f64>(&ctx, group, size));
+        }
+
+        #[cfg(feature = "half")]
+        if half {
+            run(measure::<half::f16>(&ctx, group, size));
+        }
+
+        // Plans of each size are only used once.
+        ctx.clear();
+    }
+
+    if let Some(path) = args.json {
+        std::fs::write(&path, to_json(&rows)).expect("writing JSON");
+    }
+}
@@ -1,14 +1,18 @@
-use std::sync::Arc;
 use error::check_error;
-use vulkano::{buffer::BufferAccess, VulkanObject};
-use ash::vk::Handle;
+use ash::vk;
+#[cfg(feature = "vulkano")]
+use vulkano::{SynchronizedVulkanObject, VulkanObject};
 use crate::{
-    config::{Config, ConfigGuard},
+    config::{Buffer, Config, ConfigGuard},
+    debug::DebugInfo,
     error,
+    future::FftFuture,
+    plan::PlanInfo,
+    profile::{LaunchProfile, Profiler},
 };
 
 use std::pin::Pin;
-use vk_sys as vk;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use std::ptr::addr_of_mut;
 
@@ -32,15 +36,50 @@ pub enum LaunchError {
     ConfigSpecifiesInputBuffer,
     ConfigSpecifiesOutputBuffer,
     ConfigSpecifiesKernel,
+    /// The command buffer belongs to a different device than the `App`
+    DeviceMismatch,
+    /// The `App` was configured with raw handles, but the operation needs the vulkano objects
+    ConfigNotFromVulkano,
+    /// vulkano refused to execute the recorded FFT in the primary command buffer
+    ExecuteCommands,
+    #[display(fmt = "{} is {} bytes, but the plan needs at least {}", kind, size, required)]
+    BufferTooSmall {
+        kind: BufferKind,
+        size: u64,
+        required: u64,
+    },
+    #[display(fmt = "{} belongs to a different device than the plan", _0)]
+    BufferDeviceMismatch(#[error(not(source))] BufferKind),
+    #[display(fmt = "{} is used by the plan, but given neither in the config nor at launch", _0)]
+    MissingBuffer(#[error(not(source))] BufferKind),
+    #[display(fmt = "{} was given at launch, but the plan doesn't use it", _0)]
+    UnusedBuffer(#[error(not(source))] BufferKind),
+    #[display(fmt = "{} differs from the one the shared plan was created with", _0)]
+    BufferMismatch(#[error(not(source))] BufferKind),
+}
+
+/// Names the buffer a [`LaunchError`] refers to.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    #[display(fmt = "buffer")]
+    Buffer,
+    #[display(fmt = "temp buffer")]
+    TempBuffer,
+    #[display(fmt = "input buffer")]
+    InputBuffer,
+    #[display(fmt = "output buffer")]
+    OutputBuffer,
+    #[display(fmt = "kernel")]
+    Kernel,
 }
 
 pub struct LaunchParamsBuilder {
-    command_buffer: Option<vk_sys::CommandBuffer>,
-    buffer: Option<Arc<dyn BufferAccess>>,
-    temp_buffer: Option<Arc<dyn BufferAccess>>,
-    input_buffer: Option<Arc<dyn BufferAccess>>,
-    output_buffer: Option<Arc<dyn BufferAccess>>,
-    kernel: Option<Arc<dyn BufferAccess>>,
+    command_buffer: Option<vk::CommandBuffer>,
+    buffer: Option<Buffer>,
+    temp_buffer: Option<Buffer>,
+    input_buffer: Option<Buffer>,
+    output_buffer: Option<Buffer>,
+    kernel: Option<Buffer>,
 }
 
 impl LaunchParamsBuilder {
@@ -55,6 +94,7 @@ impl LaunchParamsBuilder {
         }
     }
 
+    #[cfg(feature = "vulkano")]
     pub fn command_buffer<C>(mut self, command_buffer: &C) -> Self
         where
             C: VulkanObject<Object = vk::CommandBuffer>,
@@ -63,31 +103,65 @@ impl LaunchParamsBuilder {
         self
     }
 
-    pub fn buffer(mut self, buffer: Arc<dyn BufferAccess>) -> Self {
-        self.buffer = Some(buffer);
+    /// # Safety
+    ///
+    /// `command_buffer` must be a primary command buffer in the recording state, allocated from the
+    /// device the `App` was created with.
+    pub unsafe fn raw_command_buffer(mut self, command_buffer: vk::CommandBuffer) -> Self {
+        self.command_buffer = Some(command_buffer);
         self
     }
 
-    pub fn temp_buffer(mut self, temp_buffer: Arc<dyn BufferAccess>) -> Self {
-        self.temp_buffer = Some(temp_buffer);
+    pub fn buffer<B>(mut self, buffer: B) -> Self
+        where
+            B: Into<Buffer>,
+    {
+        self.buffer = Some(buffer.into());
         self
     }
 
-    pub fn input_buffer(mut self, input_buffer: Arc<dyn BufferAccess>) -> Self {
-        self.input_buffer = Some(input_buffer);
+    pub fn temp_buffer<B>(mut self, temp_buffer: B) -> Self
+        where
+            B: Into<Buffer>,
+    {
+        self.temp_buffer = Some(temp_buffer.into());
         self
     }
 
-    pub fn output_buffer(mut self, output_buffer: Arc<dyn BufferAccess>) -> Self {
-        self.output_buffer = Some(output_buffer);
+    pub fn input_buffer<B>(mut self, input_buffer: B) -> Self
+        where
+            B: Into<Buffer>,
+    {
+        self.input_buffer = Some(input_buffer.into());
         self
     }
 
-    pub fn kernel(mut self, kernel: Arc<dyn BufferAccess>) -> Self {
-        self.kernel = Some(kernel);
+    pub fn output_buffer<B>(mut self, output_buffer: B) -> Self
+        where
+            B: Into<Buffer>,
+    {
+        self.output_buffer = Some(output_buffer.into());
         self
     }
 
+    pub fn kernel<B>(mut self, kernel: B) -> Self
+        where
+            B: Into<Buffer>,
+    {
+        self.kernel = Some(kernel.into());
+        self
+    }
+
+    fn buffers(&self) -> [(BufferKind, &Option<Buffer>); 5] {
+        [
+            (BufferKind::Buffer, &self.buffer),
+            (BufferKind::TempBuffer, &self.temp_buffer),
+            (BufferKind::InputBuffer, &self.input_buffer),
+            (BufferKind::OutputBuffer, &self.output_buffer),
+            (BufferKind::Kernel, &self.kernel),
+        ]
+    }
+
     pub fn build(self) -> Result<LaunchParams, BuildError> {
         let command_buffer = match self.command_buffer {
             Some(command_buffer) => command_buffer,
@@ -108,29 +182,32 @@ impl LaunchParamsBuilder {
 #[repr(C)]
 pub(crate) struct LaunchParamsGuard {
     pub(crate) params: vkfft_src::VkFFTLaunchParams,
-    pub(crate) command_buffer: vk_sys::CommandBuffer,
-    pub(crate) buffer: Option<vk_sys::Buffer>,
-    pub(crate) temp_buffer: Option<vk_sys::Buffer>,
-    pub(crate) input_buffer: Option<vk_sys::Buffer>,
-    pub(crate) output_buffer: Option<vk_sys::Buffer>,
-    pub(crate) kernel: Option<vk_sys::Buffer>,
+    pub(crate) command_buffer: vk::CommandBuffer,
+    pub(crate) buffer: Option<vk::Buffer>,
+    pub(crate) temp_buffer: Option<vk::Buffer>,
+    pub(crate) input_buffer: Option<vk::Buffer>,
+    pub(crate) output_buffer: Option<vk::Buffer>,
+    pub(crate) kernel: Option<vk::Buffer>,
 }
 
 pub struct LaunchParams {
     pub command_buffer: vk::CommandBuffer,
-    pub buffer: Option<Arc<dyn BufferAccess>>,
-    pub temp_buffer: Option<Arc<dyn BufferAccess>>,
-    pub input_buffer: Option<Arc<dyn BufferAccess>>,
-    pub output_buffer: Option<Arc<dyn BufferAccess>>,
-    pub kernel: Option<Arc<dyn BufferAccess>>,
+    pub buffer: Option<Buffer>,
+    pub temp_buffer: Option<Buffer>,
+    pub input_buffer: Option<Buffer>,
+    pub output_buffer: Option<Buffer>,
+    pub kernel: Option<Buffer>,
 }
 
 impl LaunchParams {
-    fn buffer_object<B>(buffer: B) -> u64
-        where
-            B: AsRef<dyn BufferAccess>,
-    {
-        buffer.as_ref().inner().buffer.internal_object().as_raw()
+    fn buffers(&self) -> [(BufferKind, &Option<Buffer>); 5] {
+        [
+            (BufferKind::Buffer, &self.buffer),
+            (BufferKind::TempBuffer, &self.temp_buffer),
+            (BufferKind::InputBuffer, &self.input_buffer),
+            (BufferKind::OutputBuffer, &self.output_buffer),
+            (BufferKind::Kernel, &self.kernel),
+        ]
     }
 
     pub(crate) fn as_sys(&self) -> Pin<Box<LaunchParamsGuard>> {
@@ -140,11 +217,11 @@ impl LaunchParams {
             let mut res = Box::pin(LaunchParamsGuard {
                 params: zeroed(),
                 command_buffer: self.command_buffer,
-                buffer: self.buffer.as_ref().map(Self::buffer_object),
-                temp_buffer: self.temp_buffer.as_ref().map(Self::buffer_object),
-                input_buffer: self.input_buffer.as_ref().map(Self::buffer_object),
-                output_buffer: self.output_buffer.as_ref().map(Self::buffer_object),
-                kernel: self.kernel.as_ref().map(Self::buffer_object),
+                buffer: self.buffer.as_ref().map(Buffer::handle),
+                temp_buffer: self.temp_buffer.as_ref().map(Buffer::handle),
+                input_buffer: self.input_buffer.as_ref().map(Buffer::handle),
+                output_buffer: self.output_buffer.as_ref().map(Buffer::handle),
+                kernel: self.kernel.as_ref().map(Buffer::handle),
             });
 
             res.params.commandBuffer = transmute(addr_of_mut!(res.command_buffer));
@@ -180,9 +257,13 @@ impl LaunchParams {
 
 pub struct App {
     app: VkFFTApplication,
+    pub(crate) device: ash::Device,
 
     // Safety: We must keep a copy of the config to ensure our resources are kept alive
-    config: Pin<Box<ConfigGuard>>,
+    pub(crate) config: Pin<Box<ConfigGuard>>,
+
+    profiler: Option<Profiler>,
+    debug: Option<DebugInfo>,
 }
 
 impl App {
@@ -191,59 +272,524 @@ impl App {
 
         let app: VkFFTApplication = unsafe { std::mem::zeroed() };
 
-        let sys_config = config.as_sys()?;
+        let device = config.device().clone();
+
+        #[cfg(feature = "wisdom")]
+        let record = config.record_wisdom;
+
+        #[cfg(feature = "wisdom")]
+        let wisdom = crate::wisdom::PlanKey::new(&config).map(|key| {
+            let wisdom = crate::wisdom::Wisdom::global();
+            let application = wisdom.application(&key, &config.tuning);
+            (key, config.tuning, application, record && wisdom.save_applications)
+        });
+
+        #[cfg_attr(not(feature = "wisdom"), allow(unused_mut))]
+        let mut sys_config = config.into_sys()?;
+
+        #[cfg(feature = "wisdom")]
+        match &wisdom {
+            Some((_, _, Some(application), _)) => {
+                sys_config.application = application.clone();
+                sys_config.config.loadApplicationFromString = 1;
+                sys_config.config.loadApplicationString = sys_config.application.as_mut_ptr().cast();
+            }
+            Some((_, _, None, true)) => sys_config.config.saveApplicationToString = 1,
+            _ => {}
+        }
+
+        let profiler = match sys_config.profile {
+            Some((period, valid_bits)) => Some(Profiler::new(&device, period, valid_bits)?),
+            None => None,
+        };
 
         let mut res = Box::pin(Self {
             app,
+            device,
             config: sys_config,
+            profiler,
+            debug: None,
         });
 
         check_error(unsafe { initializeVkFFT(std::ptr::addr_of_mut!(res.app), res.config.config) })?;
 
+        if res.config.config.keepShaderCode != 0 {
+            res.debug = Some(unsafe { DebugInfo::take(&mut res.app) });
+        }
+
+        #[cfg(feature = "wisdom")]
+        if let (true, Some((key, tuning, _, _))) = (record, wisdom) {
+            let application = match res.config.config.saveApplicationToString {
+                0 => None,
+                _ => unsafe {
+                    let data = res.app.saveApplicationString as *const u8;
+                    (!data.is_null()).then(|| std::slice::from_raw_parts(data, res.app.applicationStringSize as usize))
+                },
+            };
+
+            crate::wisdom::Wisdom::global().record(key, tuning, application);
+        }
+
         Ok(res)
     }
 
-    pub fn launch(&mut self, params: &mut LaunchParams, inverse: bool) -> error::Result<()> {
-        use vkfft_src::VkFFTAppend;
+    /// What VkFFT decided for this plan: algorithms, passes and buffers.
+    pub fn plan_info(&self) -> PlanInfo {
+        // Safety: `App::new` only returns initialized applications.
+        unsafe { PlanInfo::new(&self.app) }
+    }
+
+    /// The generated shaders of a plan built with
+    /// [`debug_info`](crate::config::ConfigBuilder::debug_info), `None` otherwise.
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debug.as_ref()
+    }
+
+    /// Records the FFT into `params.command_buffer`.
+    ///
+    /// The returned [`RecordedFft`] keeps the launch buffers alive until the command buffer is known
+    /// to have finished executing. If the launch fails, VkFFT may have recorded part of the FFT
+    /// already, and the command buffer must not be submitted.
+    ///
+    /// For plans built with [`profile`](crate::config::ConfigBuilder::profile), timestamps are
+    /// written around the dispatches of each axis, see [`profile`](Self::profile).
+    pub fn launch(&mut self, params: &mut LaunchParams, inverse: bool) -> error::Result<RecordedFft> {
+        let launch_buffers = params.buffers();
+
+        self.resolve(&launch_buffers)?;
+        self.validate(&launch_buffers)?;
+
+        let mut sys_params = params.as_sys();
+
+        match self.profiler.take() {
+            None => append(&mut self.app, &mut sys_params, inverse)?,
+            Some(mut profiler) => {
+                let res = self.append_profiled(&mut profiler, params.command_buffer, &mut sys_params, inverse);
+                self.profiler = Some(profiler);
+                res?
+            }
+        }
+
+        if let Some(debug) = &mut self.debug {
+            // Safety: the buffers VkFFT points to live in `params` until the end of the scope.
+            unsafe { debug.update_layout(&self.app) };
+        }
+
+        // Built last, since dropping it leaks the buffers.
+        Ok(RecordedFft {
+            command_buffer: params.command_buffer,
+            buffers: [
+                &params.buffer,
+                &params.temp_buffer,
+                &params.input_buffer,
+                &params.output_buffer,
+                &params.kernel,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+        })
+    }
+
+    /// Appends the axes one at a time, with a timestamp after each. VkFFT skips the dispatches of
+    /// omitted axes at launch, while the buffers each axis reads and writes were fixed when the
+    /// plan was created, so omitting all other axes records exactly the dispatches of one axis.
+    fn append_profiled(
+        &mut self,
+        profiler: &mut Profiler,
+        command_buffer: vk::CommandBuffer,
+        params: &mut LaunchParamsGuard,
+        inverse: bool,
+    ) -> error::Result<()> {
+        profiler.begin(&self.device, command_buffer);
+
+        // The convolution runs the inverse of the other axes right after the last one.
+        if self.app.configuration.performConvolution != 0 {
+            append(&mut self.app, params, inverse)?;
+            profiler.end(&self.device, command_buffer, None);
+            return Ok(());
+        }
+
+        let plan = unsafe {
+            if inverse {
+                self.app.localFFTPlan_inverse.as_ref()
+            } else {
+                self.app.localFFTPlan.as_ref()
+            }
+        };
+
+        let omit = self.app.configuration.omitDimension;
+        let dim = (self.app.configuration.FFTdim as usize).min(3);
 
-        let mut params = params.as_sys();
+        let mut axes: Vec<usize> = (0..dim)
+            .filter(|&axis| omit[axis] == 0 && plan.is_some_and(|p| p.numAxisUploads[axis] > 0))
+            .collect();
 
-        if self.config.buffer.is_some() && params.buffer.is_some() {
-            return Err(LaunchError::ConfigSpecifiesBuffer.into());
+        if inverse {
+            axes.reverse();
         }
 
-        if self.config.temp_buffer.is_some() && params.temp_buffer.is_some() {
-            return Err(LaunchError::ConfigSpecifiesTempBuffer.into());
+        let mut res = Ok(());
+
+        for axis in axes {
+            self.app.configuration.omitDimension = [1, 1, 1];
+            self.app.configuration.omitDimension[axis] = 0;
+
+            res = append(&mut self.app, params, inverse);
+            if res.is_err() {
+                break;
+            }
+
+            profiler.end(&self.device, command_buffer, Some(axis));
         }
 
-        if self.config.input_buffer.is_some() && params.input_buffer.is_some() {
-            return Err(LaunchError::ConfigSpecifiesInputBuffer.into());
+        self.app.configuration.omitDimension = omit;
+        res
+    }
+
+    /// The GPU time of the last launch recorded by a profiled plan.
+    ///
+    /// `None` if the plan isn't profiled, nothing was launched yet or the command buffer of the
+    /// last launch hasn't finished executing. Only the most recently recorded launch is kept, so
+    /// read the profile before recording the next one.
+    pub fn profile(&self) -> error::Result<Option<LaunchProfile>> {
+        match &self.profiler {
+            Some(profiler) => profiler.read(&self.device),
+            None => Ok(None),
         }
+    }
 
-        if self.config.output_buffer.is_some() && params.output_buffer.is_some() {
-            return Err(LaunchError::ConfigSpecifiesOutputBuffer.into());
+    /// Whether the plan reads or writes a buffer of this kind:
+    ///
+    /// - `buffer` is always used. For in-place plans it holds both input and output.
+    /// - `temp_buffer` is used if the config gave one, otherwise VkFFT allocates its own if needed.
+    /// - `input_buffer` and `output_buffer` are used by out-of-place plans, i.e. if
+    ///   `input_formatted`/`output_formatted` are set (always for `Precision::HalfMemory`).
+    ///   For R2C they hold the unpadded real data.
+    /// - `kernel` is used by convolution plans.
+    fn uses(&self, kind: BufferKind) -> bool {
+        let config = &self.config.config;
+
+        match kind {
+            BufferKind::Buffer => true,
+            BufferKind::TempBuffer => config.userTempBuffer != 0,
+            BufferKind::InputBuffer => config.isInputFormatted != 0,
+            BufferKind::OutputBuffer => config.isOutputFormatted != 0,
+            BufferKind::Kernel => config.performConvolution != 0,
         }
+    }
+
+    fn config_buffer(&self, kind: BufferKind) -> Option<vk::Buffer> {
+        match kind {
+            BufferKind::Buffer => self.config.buffer,
+            BufferKind::TempBuffer => self.config.temp_buffer,
+            BufferKind::InputBuffer => self.config.input_buffer,
+            BufferKind::OutputBuffer => self.config.output_buffer,
+            BufferKind::Kernel => self.config.kernel,
+        }
+    }
 
-        check_error(unsafe {
-            VkFFTAppend(
-                std::ptr::addr_of_mut!(self.app),
-                if inverse { 1 } else { -1 },
-                std::ptr::addr_of_mut!(params.params),
-            )
-        })?;
+    /// Works out the effective buffer set of the launch: every buffer the plan uses must be given
+    /// exactly once, either in the config or at launch, and no unused buffer may be given at launch.
+    fn resolve(&self, launch_buffers: &[(BufferKind, &Option<Buffer>); 5]) -> Result<(), LaunchError> {
+        for (kind, launch_buffer) in launch_buffers {
+            let in_config = self.config_buffer(*kind).is_some();
+
+            match (self.uses(*kind), in_config, launch_buffer.is_some()) {
+                (_, true, true) => {
+                    return Err(match kind {
+                        BufferKind::Buffer => LaunchError::ConfigSpecifiesBuffer,
+                        BufferKind::TempBuffer => LaunchError::ConfigSpecifiesTempBuffer,
+                        BufferKind::InputBuffer => LaunchError::ConfigSpecifiesInputBuffer,
+                        BufferKind::OutputBuffer => LaunchError::ConfigSpecifiesOutputBuffer,
+                        BufferKind::Kernel => LaunchError::ConfigSpecifiesKernel,
+                    })
+                }
+                (true, false, false) => return Err(LaunchError::MissingBuffer(*kind)),
+                (false, false, true) => return Err(LaunchError::UnusedBuffer(*kind)),
+                _ => {}
+            }
+        }
 
         Ok(())
     }
 
-    pub fn forward(&mut self, params: &mut LaunchParams) -> error::Result<()> {
+    /// Checks the launch buffers against the sizes and device the plan was built for.
+    fn validate(&self, launch_buffers: &[(BufferKind, &Option<Buffer>); 5]) -> Result<(), LaunchError> {
+        let config = &self.config;
+
+        for (kind, buffer) in launch_buffers {
+            let buffer = match buffer {
+                Some(b) => b,
+                None => continue,
+            };
+
+            let required = match kind {
+                BufferKind::Buffer => config.buffer_size,
+                BufferKind::TempBuffer => config.temp_buffer_size,
+                BufferKind::InputBuffer => config.input_buffer_size,
+                BufferKind::OutputBuffer => config.output_buffer_size,
+                BufferKind::Kernel => config.kernel_size,
+            };
+
+            if buffer.size() < required {
+                return Err(LaunchError::BufferTooSmall {
+                    kind: *kind,
+                    size: buffer.size(),
+                    required,
+                });
+            }
+
+            if matches!(buffer.device(), Some(device) if device != config.device) {
+                return Err(LaunchError::BufferDeviceMismatch(*kind));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn forward(&mut self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
         self.launch(params, false)
     }
 
-    pub fn inverse(&mut self, params: &mut LaunchParams) -> error::Result<()> {
+    pub fn inverse(&mut self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
         self.launch(params, true)
     }
+
+    /// Records the FFT into a one-time command buffer allocated from the configured command pool,
+    /// submits it to the configured queue and blocks on the configured fence until it completes.
+    pub fn execute(&mut self, params: LaunchParamsBuilder, inverse: bool) -> error::Result<()> {
+        let submission = Submission::new(self);
+        let recorded = submission.submit(params, |params| self.launch(params, inverse))?;
+
+        submission.wait()?;
+        submission.complete(recorded)
+    }
+
+    pub fn execute_forward(&mut self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, false)
+    }
+
+    pub fn execute_inverse(&mut self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, true)
+    }
+
+    /// Like [`execute`](Self::execute), but returns a future that resolves once the GPU work has
+    /// finished instead of blocking the calling thread.
+    pub fn execute_async(&mut self, params: LaunchParamsBuilder, inverse: bool) -> error::Result<FftFuture<&mut Self>> {
+        let recorded = Submission::new(self).submit(params, |params| self.launch(params, inverse))?;
+
+        Ok(FftFuture::new(self, recorded))
+    }
+
+    pub fn execute_forward_async(&mut self, params: LaunchParamsBuilder) -> error::Result<FftFuture<&mut Self>> {
+        self.execute_async(params, false)
+    }
+
+    pub fn execute_inverse_async(&mut self, params: LaunchParamsBuilder) -> error::Result<FftFuture<&mut Self>> {
+        self.execute_async(params, true)
+    }
+
+    /// Like [`execute_async`](Self::execute_async), but the future takes the plan along and gives
+    /// it back once the GPU work has finished, so it can be spawned on an executor. The plan is
+    /// dropped if the launch fails.
+    pub fn into_execute_async(
+        mut self: Pin<Box<Self>>,
+        params: LaunchParamsBuilder,
+        inverse: bool,
+    ) -> error::Result<FftFuture<Pin<Box<Self>>>> {
+        let recorded = Submission::new(&self).submit(params, |params| self.launch(params, inverse))?;
+
+        Ok(FftFuture::new(self, recorded))
+    }
+
+    /// Resets the fence and frees the command buffer of a submission whose fence has signaled.
+    pub(crate) fn complete(&mut self, recorded: RecordedFft) -> error::Result<()> {
+        Submission::new(self).complete(recorded)
+    }
+}
+
+/// The handles needed to submit work with the configured pool, queue and fence, copied out of the
+/// config so the plans can be borrowed mutably while recording.
+pub(crate) struct Submission {
+    device: ash::Device,
+    command_pool: vk::CommandPool,
+    queue: vk::Queue,
+    fence: vk::Fence,
+    #[cfg(feature = "vulkano")]
+    vulkano_queue: Option<Arc<vulkano::device::Queue>>,
 }
 
+impl Submission {
+    pub(crate) fn new(app: &App) -> Self {
+        Self {
+            device: app.device.clone(),
+            command_pool: app.config.command_pool,
+            queue: app.config.queue,
+            fence: app.config.fence,
+            #[cfg(feature = "vulkano")]
+            vulkano_queue: app.config.keep_alive.queue.clone(),
+        }
+    }
+
+    /// Allocates a command buffer from the configured pool, records into it with `record` and
+    /// submits it with the configured fence.
+    pub(crate) fn submit<F>(&self, params: LaunchParamsBuilder, record: F) -> error::Result<RecordedFft>
+        where
+            F: FnOnce(&mut LaunchParams) -> error::Result<RecordedFft>,
+    {
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(self.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+
+        let command_buffer = unsafe { self.device.allocate_command_buffers(&allocate_info) }
+            .map_err(|_| error::Error::FailedToAllocateCommandBuffers)?[0];
+
+        let res = self.record_and_submit(command_buffer, params, record);
+
+        if res.is_err() {
+            unsafe { self.device.free_command_buffers(self.command_pool, &[command_buffer]) };
+        }
+
+        res
+    }
+
+    /// Blocks until the submission has finished.
+    pub(crate) fn wait(&self) -> error::Result<()> {
+        // A failed wait leaves the command buffer pending, in which case it can't be freed and the
+        // `RecordedFft` leaks the buffers.
+        unsafe { self.device.wait_for_fences(&[self.fence], true, u64::MAX) }
+            .map_err(|_| error::Error::FailedToWaitForFences)
+    }
+
+    /// Resets the fence and frees the command buffer of a submission whose fence has signaled.
+    pub(crate) fn complete(&self, recorded: RecordedFft) -> error::Result<()> {
+        unsafe {
+            self.device.free_command_buffers(self.command_pool, &[recorded.command_buffer]);
+            recorded.assume_completed();
+
+            self.device
+                .reset_fences(&[self.fence])
+                .map_err(|_| error::Error::FailedToResetFences)
+        }
+    }
+
+    fn record_and_submit<F>(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        params: LaunchParamsBuilder,
+        record: F,
+    ) -> error::Result<RecordedFft>
+        where
+            F: FnOnce(&mut LaunchParams) -> error::Result<RecordedFft>,
+    {
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        unsafe {
+            self.device
+                .begin_command_buffer(command_buffer, &begin_info)
+                .map_err(|_| error::Error::FailedToBeginCommandBuffer)?;
+
+            let mut params = params
+                .raw_command_buffer(command_buffer)
+                .build()
+                .expect("command buffer is set");
+
+            let recorded = record(&mut params)?;
+
+            // Nothing reaches the GPU if ending or submitting fails.
+            if let Err(e) = self.end_and_submit(command_buffer) {
+                recorded.assume_completed();
+                return Err(e);
+            }
+
+            Ok(recorded)
+        }
+    }
+
+    fn end_and_submit(&self, command_buffer: vk::CommandBuffer) -> error::Result<()> {
+        unsafe {
+            self.device
+                .end_command_buffer(command_buffer)
+                .map_err(|_| error::Error::FailedToEndCommandBuffer)?;
+
+            let command_buffers = [command_buffer];
+            let submit_info = vk::SubmitInfo::builder().command_buffers(&command_buffers);
+
+            #[cfg(feature = "vulkano")]
+            let _queue = self.vulkano_queue.as_ref().map(|q| q.internal_object_guard());
+
+            self.device
+                .queue_submit(self.queue, &[submit_info.build()], self.fence)
+                .map_err(|_| error::Error::FailedToSubmitQueue)
+        }
+    }
+}
+
+/// Keeps the buffers of a recorded FFT alive until its command buffer has finished executing.
+///
+/// The token can't tell when that is, so dropping it leaks the buffers: their references are
+/// forgotten and the device memory is never freed. Release them once the command buffer is done:
+///
+/// - [`App::execute`] and its variants submit, wait and release on their own.
+/// - [`release`](Self::release) waits for a vulkano future that includes the submission.
+/// - [`assume_completed`](Self::assume_completed) takes the caller's word, e.g. after waiting on a
+///   fence of their own or for a command buffer that is never submitted.
+#[must_use = "dropping a RecordedFft leaks its buffers"]
+pub struct RecordedFft {
+    command_buffer: vk::CommandBuffer,
+    buffers: Vec<Buffer>,
+}
+
+impl RecordedFft {
+    pub fn command_buffer(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    pub fn buffers(&self) -> &[Buffer] {
+        &self.buffers
+    }
+
+    /// Waits until `future` has finished on the GPU, then releases the buffers. `future` must
+    /// include the submission of [`command_buffer`](Self::command_buffer), e.g. the future the
+    /// primary command buffer it was recorded into was executed with.
+    #[cfg(feature = "vulkano")]
+    pub fn release<F: vulkano::sync::GpuFuture>(self, future: F) -> error::Result<()> {
+        future
+            .then_signal_fence_and_flush()
+            .map_err(|_| error::Error::FailedToSubmitQueue)?
+            .wait(None)
+            .map_err(|_| error::Error::FailedToWaitForFences)?;
+
+        unsafe { self.assume_completed() };
+        Ok(())
+    }
+
+    /// Releases the buffers without waiting.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must have finished executing, or must never be submitted.
+    pub unsafe fn assume_completed(mut self) {
+        self.buffers.clear();
+    }
+}
+
+impl Drop for RecordedFft {
+    fn drop(&mut self) {
+        for buffer in self.buffers.drain(..) {
+            std::mem::forget(buffer);
+        }
+    }
+}
+
+// Safety: `VkFFTApplication` only holds heap allocations owned by VkFFT and Vulkan handles, none of
+// which are tied to the thread that created them. Every method that touches them takes `&mut self`.
+unsafe impl Send for App {}
+
 impl Drop for App {
     fn drop(&mut self) {
         use vkfft_src::*;
@@ -251,5 +797,123 @@ impl Drop for App {
         unsafe {
             deleteVkFFT(std::ptr::addr_of_mut!(self.app));
         }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.destroy(&self.device);
+        }
+    }
+}
+
+fn append(app: &mut VkFFTApplication, params: &mut LaunchParamsGuard, inverse: bool) -> error::Result<()> {
+    check_error(unsafe {
+        VkFFTAppend(
+            app,
+            if inverse { 1 } else { -1 },
+            std::ptr::addr_of_mut!(params.params),
+        )
+    })
+}
+
+/// An [`App`] that can be shared between threads, e.g. behind an `Arc`.
+///
+/// Launches are serialized by an internal lock. Launching with other buffers than an earlier launch
+/// would rewrite the descriptor sets of the plan, which invalidates command buffers recorded before
+/// that haven't finished executing yet. So the launch buffers are bound when the `SharedApp` is
+/// created, and launches with other buffers fail with [`LaunchError::BufferMismatch`].
+pub struct SharedApp {
+    app: Mutex<Pin<Box<App>>>,
+    /// The launch buffers of every launch: buffer, temp buffer, input, output and kernel.
+    buffers: [Option<Buffer>; 5],
+}
+
+impl SharedApp {
+    /// A shared plan that takes all its buffers from `config`.
+    pub fn new(config: Config) -> error::Result<Arc<Self>> {
+        Ok(Arc::new(App::new(config)?.into()))
+    }
+
+    /// A shared plan whose launches all use the buffers of `buffers`, for the buffers the plan
+    /// uses that `config` doesn't give. The command buffer of `buffers` is ignored.
+    pub fn with_buffers(config: Config, buffers: LaunchParamsBuilder) -> error::Result<Arc<Self>> {
+        let app = App::new(config)?;
+
+        app.resolve(&buffers.buffers())?;
+        app.validate(&buffers.buffers())?;
+
+        Ok(Arc::new(Self {
+            app: Mutex::new(app),
+            buffers: buffers.buffers().map(|(_, buffer)| buffer.clone()),
+        }))
     }
-}
\ No newline at end of file
+
+    fn lock(&self) -> MutexGuard<'_, Pin<Box<App>>> {
+        // A panic while recording doesn't leave the plan itself in an inconsistent state.
+        self.app.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Checks that a launch uses the bound buffers.
+    fn check(&self, launch_buffers: [(BufferKind, &Option<Buffer>); 5]) -> Result<(), LaunchError> {
+        for ((kind, buffer), bound) in launch_buffers.into_iter().zip(&self.buffers) {
+            if buffer.as_ref().map(Buffer::handle) != bound.as_ref().map(Buffer::handle) {
+                return Err(LaunchError::BufferMismatch(kind));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn launch(&self, params: &mut LaunchParams, inverse: bool) -> error::Result<RecordedFft> {
+        self.check(params.buffers())?;
+        self.lock().launch(params, inverse)
+    }
+
+    pub fn forward(&self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
+        self.launch(params, false)
+    }
+
+    pub fn inverse(&self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
+        self.launch(params, true)
+    }
+
+    pub fn profile(&self) -> error::Result<Option<LaunchProfile>> {
+        self.lock().profile()
+    }
+
+    pub fn plan_info(&self) -> PlanInfo {
+        self.lock().plan_info()
+    }
+
+    pub fn debug_info(&self) -> Option<DebugInfo> {
+        self.lock().debug_info().cloned()
+    }
+
+    /// Records a launch into a secondary vulkano command buffer, see
+    /// [`FftCommandBuffer::record`](crate::command_buffer::FftCommandBuffer::record).
+    #[cfg(feature = "vulkano")]
+    pub fn record(&self, params: LaunchParamsBuilder, inverse: bool) -> error::Result<crate::command_buffer::FftCommandBuffer> {
+        self.check(params.buffers())?;
+        crate::command_buffer::FftCommandBuffer::record(&mut self.lock(), params, inverse)
+    }
+
+    pub fn execute(&self, params: LaunchParamsBuilder, inverse: bool) -> error::Result<()> {
+        self.check(params.buffers())?;
+        self.lock().execute(params, inverse)
+    }
+
+    pub fn execute_forward(&self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, false)
+    }
+
+    pub fn execute_inverse(&self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, true)
+    }
+}
+
+impl From<Pin<Box<App>>> for SharedApp {
+    fn from(app: Pin<Box<App>>) -> Self {
+        Self {
+            app: Mutex::new(app),
+            buffers: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Transforms on `ndarray` arrays.
+//!
+//! The layout of the view is passed to VkFFT as is when it can be expressed with VkFFT's strides:
+//! the fastest varying axis must be contiguous, and besides up to three VkFFT dimensions there may
+//! be one batch dimension made of axes that aren't transformed. Other views, e.g. with negative
+//! strides, are copied into a layout that fits first. Either way the result is a new array with the
+//! same axis order as the view.
+//!
+//! Like numpy's `rfftn`, [`rfft`] halves the last of the transformed axes, and the inverse
+//! transforms are normalized.
+
+use derive_more::{Display, Error};
+use ndarray::{Array, ArrayView, Dimension, IxDyn, ShapeBuilder};
+
+use crate::{
+    element::{ComplexElement, Element},
+    error,
+    host::{Context, Data, PlanKey},
+};
+
+#[derive(Display, Debug, Error)]
+pub enum ArrayError {
+    /// The transformed axes are empty, out of range or repeated
+    InvalidAxes,
+    /// More than three axes are transformed
+    TooManyAxes,
+    /// The halved axis of the complex input of [`irfft`] doesn't match the real length
+    LengthMismatch,
+    /// An axis of the array has length zero
+    EmptyAxis,
+    /// The layout of the array can't be mapped to VkFFT's strides
+    UnsupportedLayout,
+    /// The result doesn't fit the shape of the input
+    Shape(ndarray::ShapeError),
+}
+
+/// One VkFFT dimension, made of array axes that are contiguous with each other.
+struct Dim {
+    len: u64,
+    stride: u64,
+    transform: bool,
+    axes: Vec<usize>,
+}
+
+enum Kind {
+    C2C { inverse: bool },
+    R2C,
+    C2R { len: usize },
+}
+
+/// Forward complex transform of `input` along `axes`.
+pub fn fft<C, D>(ctx: &Context, input: ArrayView<C, D>, axes: &[usize]) -> error::Result<Array<C, D>>
+    where
+        C: ComplexElement,
+        D: Dimension,
+{
+    transform(ctx, input, axes, Kind::C2C { inverse: false })
+}
+
+/// Normalized inverse complex transform of `input` along `axes`.
+pub fn ifft<C, D>(ctx: &Context, input: ArrayView<C, D>, axes: &[usize]) -> error::Result<Array<C, D>>
+    where
+        C: ComplexElement,
+        D: Dimension,
+{
+    transform(ctx, input, axes, Kind::C2C { inverse: true })
+}
+
+/// Forward transform of real `input` along `axes`. The last of `axes` is halved to `n / 2 + 1`
+/// elements in the result.
+pub fn rfft<C, D>(ctx: &Context, input: ArrayView<C::Real, D>, axes: &[usize]) -> error::Result<Array<C, D>>
+    where
+        C: ComplexElement,
+        D: Dimension,
+{
+    transform(ctx, input, axes, Kind::R2C)
+}
+
+/// Normalized inverse of [`rfft`]. `len` is the real length of the last of `axes`, whose complex
+/// length must be `len / 2 + 1`.
+pub fn irfft<C, D>(ctx: &Context, input: ArrayView<C, D>, axes: &[usize], len: usize) -> error::Result<Array<C::Real, D>>
+    where
+        C: ComplexElement,
+        D: Dimension,
+{
+    transform(ctx, input, axes, Kind::C2R { len })
+}
+
+fn transform<A, B, D>(ctx: &Context, input: ArrayView<A, D>, axes: &[usize], kind: Kind) -> error::Result<Array<B, D>>
+    where
+        A: Element,
+        B: Element,
+        D: Dimension,
+{
+    let ndim = input.ndim();
+    let last = check(input.shape(), axes, &kind)?;
+
+    let halved = !matches!(kind, Kind::C2C { .. });
+    let input = input.into_dyn();
+
+    // The view as is, or a copy with the batch axes first and the transformed axes last, in the
+    // order given, which always fits.
+    let (work, perm) = match layout(input.shape(), input.strides(), axes, halved.then_some(last)) {
+        Some(dims) => (Work::View(input, dims), None),
+        None => {
+            let perm: Vec<usize> = (0..ndim)
+                .filter(|axis| !axes.contains(axis))
+                .chain(axes.iter().copied())
+                .collect();
+
+            let copy = input.permuted_axes(perm.clone()).as_standard_layout().into_owned();
+            let work_axes: Vec<usize> = (ndim - axes.len()..ndim).collect();
+            let dims = layout(copy.shape(), copy.strides(), &work_axes, halved.then_some(ndim - 1))
+                .ok_or(ArrayError::UnsupportedLayout)?;
+
+            (Work::Copy(copy, dims), Some(perm))
+        }
+    };
+
+    let (view, dims) = work.parts();
+    let key_dims = &dims[..dims.len().min(3)];
+
+    let mut size = [1; 3];
+    let mut omit = [false; 3];
+
+    for (i, dim) in key_dims.iter().enumerate() {
+        size[i] = dim.len;
+        omit[i] = !dim.transform;
+    }
+
+    let fft_dim = key_dims.iter().rposition(|d| d.transform).unwrap_or(0) + 1;
+    let batches = dims.get(3).map(|d| d.len).unwrap_or(1);
+
+    // The length of x as seen by VkFFT, which is the real length for R2C and C2R.
+    let x = match kind {
+        Kind::C2R { len } => len as u64,
+        _ => size[0],
+    };
+    size[0] = x;
+
+    let view_stride = strides(dims);
+    let other_x = match kind {
+        Kind::C2C { .. } => x,
+        Kind::R2C => PlanKey::complex_x(x, true),
+        Kind::C2R { .. } => x,
+    };
+    let other_stride = match kind {
+        Kind::C2C { .. } => view_stride,
+        _ => PlanKey::packed(other_x, &size),
+    };
+
+    let (stride, input_stride) = match kind {
+        Kind::R2C => (other_stride, view_stride),
+        _ => (view_stride, other_stride),
+    };
+
+    let key = PlanKey {
+        fft_dim,
+        size,
+        omit,
+        batches,
+        precision: A::PRECISION,
+        r2c: halved,
+        stride,
+        input_stride,
+    };
+    key.check()?;
+
+    // Input elements at the offsets given by the view's strides, or the layout found above.
+    let mut data = vec![A::zeroed(); span(view.shape(), view.strides())];
+    for (index, value) in view.indexed_iter() {
+        data[offset(index.slice(), view.strides())] = *value;
+    }
+
+    let mut out_shape = view.shape().to_vec();
+    let out_strides: Vec<usize> = match kind {
+        Kind::C2C { .. } => view.strides().iter().map(|&s| s.max(0) as usize).collect(),
+        _ => mapped_strides(view.shape(), dims, &other_stride),
+    };
+
+    let inverse = match kind {
+        Kind::C2C { inverse } => inverse,
+        Kind::R2C => false,
+        Kind::C2R { .. } => true,
+    };
+
+    let output: Vec<B> = match kind {
+        Kind::C2C { .. } => {
+            ctx.run(key, inverse, Data::InOut(bytemuck::cast_slice_mut(&mut data)), None)?;
+            bytemuck::cast_slice(&data).to_vec()
+        }
+        Kind::R2C => {
+            let mut output = vec![B::zeroed(); key.buffer_len()];
+            ctx.run(
+                key,
+                false,
+                Data::Out(bytemuck::cast_slice_mut(&mut output)),
+                Some(Data::In(bytemuck::cast_slice(&data))),
+            )?;
+            output
+        }
+        Kind::C2R { .. } => {
+            let mut output = vec![B::zeroed(); key.input_len()];
+            ctx.run(
+                key,
+                true,
+                Data::In(bytemuck::cast_slice(&data)),
+                Some(Data::Out(bytemuck::cast_slice_mut(&mut output))),
+            )?;
+            output
+        }
+    };
+
+    let halved_axis = dims[0].axes[0];
+    match kind {
+        Kind::R2C => out_shape[halved_axis] = other_x as usize,
+        Kind::C2R { len } => out_shape[halved_axis] = len,
+        Kind::C2C { .. } => {}
+    }
+
+    let output = Array::from_shape_vec(IxDyn(&out_shape).strides(IxDyn(&out_strides)), output)
+        .map_err(ArrayError::Shape)?;
+
+    let output = match perm {
+        Some(perm) => {
+            let mut inverse_perm = vec![0; ndim];
+            for (i, &axis) in perm.iter().enumerate() {
+                inverse_perm[axis] = i;
+            }
+
+            output.permuted_axes(inverse_perm)
+        }
+        None => output,
+    };
+
+    Ok(output.into_dimensionality().map_err(ArrayError::Shape)?)
+}
+
+/// Checks the transformed axes against the shape and returns the last of them.
+fn check(shape: &[usize], axes: &[usize], kind: &Kind) -> Result<usize, ArrayError> {
+    let mut seen = vec![false; shape.len()];
+
+    for &axis in axes {
+        if axis >= shape.len() || std::mem::replace(&mut seen[axis], true) {
+            return Err(ArrayError::InvalidAxes);
+        }
+    }
+
+    let last = match axes.last() {
+        Some(&last) => last,
+        None => return Err(ArrayError::InvalidAxes),
+    };
+
+    if axes.len() > 3 {
+        return Err(ArrayError::TooManyAxes);
+    }
+
+    if shape.contains(&0) {
+        return Err(ArrayError::EmptyAxis);
+    }
+
+    if let Kind::C2R { len } = kind {
+        if shape[last] != len / 2 + 1 {
+            return Err(ArrayError::LengthMismatch);
+        }
+    }
+
+    Ok(last)
+}
+
+enum Work<'a, A> {
+    View(ArrayView<'a, A, IxDyn>, Vec<Dim>),
+    Copy(Array<A, IxDyn>, Vec<Dim>),
+}
+
+impl<'a, A> Work<'a, A> {
+    fn parts(&self) -> (ArrayView<'_, A, IxDyn>, &[Dim]) {
+        match self {
+            Work::View(view, dims) => (view.view(), dims),
+            Work::Copy(copy, dims) => (copy.view(), dims),
+        }
+    }
+}
+
+/// Maps the array layout to VkFFT dimensions in memory order, or `None` if VkFFT can't express it.
+/// `halved` is the axis R2C must find contiguous in dimension 0.
+fn layout(shape: &[usize], strides: &[isize], axes: &[usize], halved: Option<usize>) -> Option<Vec<Dim>> {
+    let mut order: Vec<usize> = (0..shape.len())
+        .filter(|&axis| shape[axis] > 1 || axes.contains(&axis))
+        .collect();
+
+    if order.iter().any(|&axis| strides[axis] <= 0 && shape[axis] > 1) {
+        return None;
+    }
+
+    order.sort_by_key(|&axis| strides[axis].max(0));
+
+    let mut dims: Vec<Dim> = Vec::new();
+    // End of the memory covered by the dimensions so far, which the next one must not overlap.
+    let mut extent = 1;
+
+    for axis in order {
+        let len = shape[axis] as u64;
+        let stride = strides[axis].max(0) as u64;
+        let transform = axes.contains(&axis);
+
+        if len > 1 && !dims.is_empty() && stride < extent {
+            return None;
+        }
+
+        match dims.last_mut() {
+            Some(last) if !transform && !last.transform && stride == last.stride * last.len => {
+                last.len *= len;
+                last.axes.push(axis);
+            }
+            _ => dims.push(Dim {
+                len,
+                stride: if dims.is_empty() { 1 } else { stride },
+                transform,
+                axes: vec![axis],
+            }),
+        }
+
+        extent = extent.max(stride * len);
+    }
+
+    let fits = match dims.first() {
+        Some(first) => strides[first.axes[0]] == 1 || shape[first.axes[0]] == 1,
+        None => false,
+    };
+
+    let batch_fits = dims.len() <= 3 || (dims.len() == 4 && !dims[3].transform);
+
+    let halved_fits = match halved {
+        Some(axis) => dims[0].axes == [axis],
+        None => true,
+    };
+
+    // Copying is cheaper than transforming a view full of holes.
+    let elements: usize = shape.iter().product();
+    let compact = span(shape, strides) <= 2 * elements;
+
+    (fits && batch_fits && halved_fits && compact).then_some(dims)
+}
+
+/// VkFFT strides of y, z and the batch, continuing packed past the last dimension.
+fn strides(dims: &[Dim]) -> [u64; 3] {
+    let mut res = [0; 3];
+    let mut next = 1;
+
+    for (i, stride) in res.iter_mut().enumerate() {
+        let dim = dims.get(i);
+        next = dim.map(|d| d.stride * d.len).unwrap_or(next);
+        *stride = dims.get(i + 1).map(|d| d.stride).unwrap_or(next);
+    }
+
+    res
+}
+
+/// Strides of the array axes in a buffer whose VkFFT strides are `stride`.
+fn mapped_strides(shape: &[usize], dims: &[Dim], stride: &[u64; 3]) -> Vec<usize> {
+    let mut res = vec![0; shape.len()];
+    let dim_strides = [1, stride[0], stride[1], stride[2]];
+
+    for (dim, dim_stride) in dims.iter().zip(dim_strides) {
+        let mut axis_stride = dim_stride as usize;
+
+        for &axis in &dim.axes {
+            res[axis] = axis_stride;
+            axis_stride *= shape[axis];
+        }
+    }
+
+    res
+}
+
+fn span(shape: &[usize], strides: &[isize]) -> usize {
+    1 + shape
+        .iter()
+        .zip(strides)
+        .map(|(&len, &stride)| len.saturating_sub(1) * stride.max(0) as usize)
+        .sum::<usize>()
+}
+
+fn offset(index: &[usize], strides: &[isize]) -> usize {
+    index.iter().zip(strides).map(|(&i, &s)| i * s.max(0) as usize).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axes(dims: &[Dim]) -> Vec<Vec<usize>> {
+        dims.iter().map(|dim| dim.axes.clone()).collect()
+    }
+
+    #[test]
+    fn c_and_fortran_order() {
+        let dims = layout(&[4, 5, 6], &[30, 6, 1], &[0, 1, 2], None).unwrap();
+        assert_eq!(axes(&dims), [[2], [1], [0]]);
+        assert_eq!(strides(&dims), [6, 30, 120]);
+        assert_eq!(mapped_strides(&[4, 5, 6], &dims, &strides(&dims)), [30, 6, 1]);
+
+        let dims = layout(&[4, 5, 6], &[1, 4, 20], &[0, 1, 2], None).unwrap();
+        assert_eq!(axes(&dims), [[0], [1], [2]]);
+        assert_eq!(strides(&dims), [4, 20, 120]);
+        assert_eq!(mapped_strides(&[4, 5, 6], &dims, &strides(&dims)), [1, 4, 20]);
+    }
+
+    #[test]
+    fn non_contiguous_views() {
+        // Every other row of a 4 x 12 array.
+        let dims = layout(&[4, 6], &[12, 1], &[1], None).unwrap();
+        assert_eq!(axes(&dims), [[1], [0]]);
+        assert!(!dims[1].transform);
+        assert_eq!(strides(&dims), [12, 48, 48]);
+
+        // Too many holes, a strided fastest axis, and axes overlapping in memory.
+        assert!(layout(&[4, 6], &[100, 1], &[1], None).is_none());
+        assert!(layout(&[4, 6], &[12, 2], &[1], None).is_none());
+        assert!(layout(&[4, 6], &[3, 1], &[1], None).is_none());
+    }
+
+    #[test]
+    fn negative_strides() {
+        assert!(layout(&[4, 6], &[-6, 1], &[1], None).is_none());
+        assert!(layout(&[4, 6], &[6, -1], &[1], None).is_none());
+
+        // Length one axes are never stepped through.
+        assert!(layout(&[1, 6], &[-6, 1], &[1], None).is_some());
+
+        assert_eq!(span(&[4, 6], &[-6, 1]), 6);
+        assert_eq!(offset(&[3, 2], &[-6, 1]), 2);
+    }
+
+    #[test]
+    fn batch_axes() {
+        // Axes that aren't transformed merge into one batch dimension when they're contiguous.
+        let dims = layout(&[2, 3, 8], &[24, 8, 1], &[2], None).unwrap();
+        assert_eq!(axes(&dims), [vec![2], vec![1, 0]]);
+        assert_eq!(dims[1].len, 6);
+        assert_eq!(strides(&dims), [8, 48, 48]);
+        assert_eq!(mapped_strides(&[2, 3, 8], &dims, &strides(&dims)), [24, 8, 1]);
+
+        // The batch must come after all transformed dimensions.
+        assert!(layout(&[2, 3, 4, 5], &[60, 20, 5, 1], &[0, 1, 2], None).is_none());
+        assert!(layout(&[2, 3, 4, 5], &[60, 20, 5, 1], &[1, 2, 3], None).is_some());
+    }
+
+    #[test]
+    fn halved_axis() {
+        // R2C halves the last transformed axis, which must be the contiguous one.
+        assert!(layout(&[4, 6], &[6, 1], &[0], Some(0)).is_none());
+
+        let dims = layout(&[4, 6], &[6, 1], &[0, 1], Some(1)).unwrap();
+        assert_eq!(axes(&dims), [[1], [0]]);
+
+        // The complex side holds 6 / 2 + 1 = 4 elements per row.
+        assert_eq!(mapped_strides(&[4, 4], &dims, &[4, 16, 16]), [4, 1]);
+    }
+
+    #[test]
+    fn spans_and_offsets() {
+        assert_eq!(span(&[4, 6], &[6, 1]), 24);
+        assert_eq!(span(&[4, 6], &[12, 1]), 42);
+        assert_eq!(offset(&[1, 2], &[6, 1]), 8);
+        assert_eq!(offset(&[1, 2], &[1, 4]), 9);
+    }
+
+    #[test]
+    fn checked_axes() {
+        let c2c = Kind::C2C { inverse: false };
+
+        assert_eq!(check(&[4, 6], &[0, 1], &c2c).unwrap(), 1);
+        assert!(matches!(check(&[4, 6], &[], &c2c), Err(ArrayError::InvalidAxes)));
+        assert!(matches!(check(&[4, 6], &[2], &c2c), Err(ArrayError::InvalidAxes)));
+        assert!(matches!(check(&[4, 6], &[1, 1], &c2c), Err(ArrayError::InvalidAxes)));
+        assert!(matches!(check(&[2, 2, 2, 2], &[0, 1, 2, 3], &c2c), Err(ArrayError::TooManyAxes)));
+        assert!(matches!(check(&[0, 6], &[1], &c2c), Err(ArrayError::EmptyAxis)));
+
+        assert!(check(&[4, 4], &[1], &Kind::C2R { len: 6 }).is_ok());
+        assert!(matches!(check(&[4, 4], &[1], &Kind::C2R { len: 8 }), Err(ArrayError::LengthMismatch)));
+    }
+}
@@ -0,0 +1,210 @@
+//! Searching [`TuningOptions`] for a device and shape, and keeping the winners in a JSON file that
+//! [`ConfigBuilder::tuning_file`] reads back.
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+
+use derive_more::{Display, Error};
+use serde::{Deserialize, Serialize};
+use vulkano::device::physical::PhysicalDevice;
+
+use crate::{
+    app::{App, LaunchParams},
+    config::{ConfigBuilder, Precision, TuningOptions},
+    error,
+    plan::PlanInfo,
+};
+
+#[derive(Display, Debug, Error)]
+pub enum TuningError {
+    /// The config has no vulkano physical device to identify the device by
+    NoPhysicalDevice,
+    /// None of the candidates could be built and launched
+    NoCandidate,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+/// The device and shape a tuning applies to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TuningKey {
+    /// The device UUID in hex, or `vendor:device` IDs if the driver doesn't report one.
+    pub device: String,
+    pub size: Vec<u64>,
+    pub precision: Precision,
+    pub r2c: bool,
+    pub batch_count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TuningEntry {
+    pub key: TuningKey,
+    pub tuning: TuningOptions,
+    /// GPU time of a forward transform with `tuning`.
+    pub time: Duration,
+}
+
+/// The tunings found for each device and shape, stored as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TuningFile {
+    pub entries: Vec<TuningEntry>,
+}
+
+impl TuningFile {
+    /// Reads the file at `path`, or returns an empty one if there is none yet.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TuningError> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(TuningError::Json),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(TuningError::Io(e)),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TuningError> {
+        let json = serde_json::to_string_pretty(self).map_err(TuningError::Json)?;
+        std::fs::write(path, json).map_err(TuningError::Io)
+    }
+
+    pub fn get(&self, key: &TuningKey) -> Option<&TuningEntry> {
+        self.entries.iter().find(|e| &e.key == key)
+    }
+
+    /// Adds `entry`, replacing the entry with the same key.
+    pub fn insert(&mut self, entry: TuningEntry) {
+        self.entries.retain(|e| e.key != entry.key);
+        self.entries.push(entry);
+    }
+}
+
+/// Identifies the physical device across processes.
+pub(crate) fn device_id(physical_device: &PhysicalDevice) -> String {
+    let properties = physical_device.properties();
+
+    match properties.device_uuid {
+        Some(uuid) => uuid.iter().map(|b| format!("{:02x}", b)).collect(),
+        None => format!("{:04x}:{:04x}", properties.vendor_id, properties.device_id),
+    }
+}
+
+/// The values tried for one parameter, the others kept at `best`. `None` is VkFFT's own choice.
+fn candidates(best: &TuningOptions, parameter: usize) -> Vec<TuningOptions> {
+    match parameter {
+        0 => [None, Some(64), Some(128), Some(256)]
+            .map(|v| TuningOptions { aim_threads: v, ..*best })
+            .to_vec(),
+        1 => [None, Some(32), Some(64), Some(128)]
+            .map(|v| TuningOptions { coalesced_memory: v, ..*best })
+            .to_vec(),
+        2 => [None, Some(1), Some(2), Some(4)]
+            .map(|v| TuningOptions { register_boost: v, ..*best })
+            .to_vec(),
+        3 => [None, Some(false), Some(true)]
+            .map(|v| TuningOptions { use_lut: v, ..*best })
+            .to_vec(),
+        4 => [None, Some(false), Some(true)]
+            .map(|v| TuningOptions { use_lut_4_step: v, ..*best })
+            .to_vec(),
+        5 => [None, Some(131072), Some(262144), Some(524288), Some(1048576)]
+            .map(|v| TuningOptions { swap_to_3_stage_4_step: v, ..*best })
+            .to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// The profiled plan of `builder` with `tuning`, `None` if VkFFT rejects it. Candidates are never
+/// recorded into the wisdom.
+fn plan(builder: &ConfigBuilder, tuning: TuningOptions) -> Option<Pin<Box<App>>> {
+    #[cfg_attr(not(feature = "wisdom"), allow(unused_mut))]
+    let mut config = builder.clone().tuning(tuning).profile().build().ok()?;
+
+    #[cfg(feature = "wisdom")]
+    {
+        config.record_wisdom = false;
+    }
+
+    App::new(config).ok()
+}
+
+/// The fastest GPU time of `iterations` forward transforms after one to warm up, `None` if the
+/// plan can't be launched.
+fn measure(app: &mut App, iterations: u32) -> Option<Duration> {
+    app.execute_forward(LaunchParams::builder()).ok()?;
+
+    let mut best = None;
+
+    for _ in 0..iterations.max(1) {
+        app.execute_forward(LaunchParams::builder()).ok()?;
+
+        if let Some(profile) = app.profile().ok()? {
+            best = Some(best.map_or(profile.total, |t: Duration| t.min(profile.total)));
+        }
+    }
+
+    best
+}
+
+/// Searches the tuning parameters for the plan `builder` describes, trying the values of aim
+/// threads, coalesced memory, register boost, lookup tables, four-step lookup tables and the
+/// three-pass four-step switch one parameter at a time while keeping the best values of the others.
+///
+/// The candidates overwrite the data in the buffers of `builder`. Candidates VkFFT rejects are
+/// skipped, as are candidates VkFFT builds the same plan for as for the best one so far, e.g.
+/// four-step switches for sequences that fit into one pass. With the `wisdom` feature, the winner
+/// is recorded into [`Wisdom::global`](crate::wisdom::Wisdom::global).
+pub fn autotune(builder: &ConfigBuilder, iterations: u32) -> error::Result<TuningEntry> {
+    let key = builder.tuning_key()?.ok_or(TuningError::NoPhysicalDevice)?;
+
+    let mut best = TuningOptions::default();
+    let mut best_time = None;
+    let mut best_info: Option<PlanInfo> = None;
+
+    for parameter in 0.. {
+        let candidates = candidates(&best, parameter);
+        if candidates.is_empty() {
+            break;
+        }
+
+        for tuning in candidates {
+            if best_time.is_some() && tuning == best {
+                continue;
+            }
+
+            let mut app = match plan(builder, tuning) {
+                Some(app) => app,
+                None => continue,
+            };
+
+            let info = app.plan_info();
+            if best_info.as_ref() == Some(&info) {
+                continue;
+            }
+
+            if let Some(time) = measure(&mut app, iterations) {
+                if best_time.map_or(true, |t| time < t) {
+                    best = tuning;
+                    best_time = Some(time);
+                    best_info = Some(info);
+                }
+            }
+        }
+    }
+
+    let time = best_time.ok_or(TuningError::NoCandidate)?;
+
+    #[cfg(feature = "wisdom")]
+    if let Some(plan) = crate::wisdom::PlanKey::new(&builder.clone().tuning(best).build()?) {
+        crate::wisdom::Wisdom::global().record(plan, best, None);
+    }
+
+    Ok(TuningEntry { key, tuning: best, time })
+}
+
+/// Like [`autotune`], and adds the result to `file`, replacing an earlier tuning of the same device
+/// and shape. Saving `file` keeps it for [`ConfigBuilder::tuning_file`].
+pub fn autotune_into(file: &mut TuningFile, builder: &ConfigBuilder, iterations: u32) -> error::Result<TuningEntry> {
+    let entry = autotune(builder, iterations)?;
+    file.insert(entry.clone());
+    Ok(entry)
+}
@@ -0,0 +1,230 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use vulkano::{
+    buffer::BufferAccess,
+    command_buffer::{
+        pool::{standard::StandardCommandPoolAlloc, CommandPool, CommandPoolBuilderAlloc},
+        sys::{CommandBufferBeginInfo, UnsafeCommandBuffer, UnsafeCommandBufferBuilder},
+        AutoCommandBufferBuilder, CommandBufferExecError, CommandBufferInheritanceInfo, CommandBufferLevel,
+        CommandBufferUsage, PrimaryAutoCommandBuffer, SecondaryCommandBuffer,
+    },
+    device::{Device, DeviceOwned},
+    image::{ImageAccess, ImageLayout, ImageSubresourceRange},
+    sync::{AccessFlags, PipelineMemoryAccess, PipelineStages},
+    DeviceSize, VulkanObject,
+};
+
+use crate::{
+    app::{App, LaunchError, LaunchParamsBuilder},
+    config::Buffer,
+    error,
+};
+
+/// Records VkFFT dispatches into a vulkano [`AutoCommandBufferBuilder`].
+///
+/// The dispatch is recorded into a secondary command buffer that reports every vulkano buffer it
+/// touches, so vulkano inserts the barriers and keeps the buffers alive like for its own commands.
+/// Buffers passed with [`Buffer::from_raw`] are not tracked.
+pub trait FftCommandBufferBuilderExt {
+    fn fft(&mut self, app: &mut App, params: LaunchParamsBuilder, inverse: bool) -> error::Result<&mut Self>;
+
+    fn fft_forward(&mut self, app: &mut App, params: LaunchParamsBuilder) -> error::Result<&mut Self> {
+        self.fft(app, params, false)
+    }
+
+    fn fft_inverse(&mut self, app: &mut App, params: LaunchParamsBuilder) -> error::Result<&mut Self> {
+        self.fft(app, params, true)
+    }
+}
+
+impl<P> FftCommandBufferBuilderExt for AutoCommandBufferBuilder<PrimaryAutoCommandBuffer<P::Alloc>, P>
+    where
+        P: CommandPoolBuilderAlloc,
+{
+    fn fft(&mut self, app: &mut App, params: LaunchParamsBuilder, inverse: bool) -> error::Result<&mut Self> {
+        if app.config.device != self.device().internal_object() {
+            return Err(LaunchError::DeviceMismatch.into());
+        }
+
+        let command_buffer = FftCommandBuffer::record(app, params, inverse)?;
+
+        self.execute_commands(command_buffer)
+            .map_err(|_| LaunchError::ExecuteCommands)?;
+
+        Ok(self)
+    }
+}
+
+/// A secondary command buffer holding one VkFFT dispatch.
+pub struct FftCommandBuffer {
+    inner: UnsafeCommandBuffer,
+    // Must be dropped after `inner`.
+    _alloc: StandardCommandPoolAlloc,
+    inheritance_info: CommandBufferInheritanceInfo,
+    buffers: Vec<(Arc<dyn BufferAccess>, Range<DeviceSize>, PipelineMemoryAccess)>,
+    in_use: AtomicBool,
+}
+
+impl FftCommandBuffer {
+    /// Records a dispatch of `app` into a new secondary command buffer allocated from the device's
+    /// standard command pool for the queue family of the queue the `App` was created with.
+    pub fn record(app: &mut App, params: LaunchParamsBuilder, inverse: bool) -> error::Result<Self> {
+        let keep_alive = &app.config.keep_alive;
+
+        let (device, queue_family_index) = match (&keep_alive.device, &keep_alive.queue) {
+            (Some(device), Some(queue)) => (device.clone(), queue.queue_family_index()),
+            _ => return Err(LaunchError::ConfigNotFromVulkano.into()),
+        };
+
+        let config_buffers = [
+            (&keep_alive.buffer, false),
+            (&keep_alive.temp_buffer, false),
+            (&keep_alive.input_buffer, false),
+            (&keep_alive.output_buffer, false),
+            (&keep_alive.kernel, true),
+        ];
+
+        let mut buffers: Vec<_> = config_buffers
+            .into_iter()
+            .filter_map(|(b, read_only)| b.as_ref().map(|b| (b.clone(), read_only)))
+            .collect();
+
+        let alloc = Self::allocate(&device, queue_family_index)?;
+
+        let inheritance_info = CommandBufferInheritanceInfo::default();
+
+        let inner = unsafe {
+            let builder = UnsafeCommandBufferBuilder::new(
+                alloc.inner(),
+                CommandBufferBeginInfo {
+                    usage: CommandBufferUsage::MultipleSubmit,
+                    inheritance_info: Some(inheritance_info.clone()),
+                    ..Default::default()
+                },
+            )
+            .map_err(|_| error::Error::FailedToBeginCommandBuffer)?;
+
+            let mut params = params
+                .raw_command_buffer(builder.internal_object())
+                .build()
+                .expect("command buffer is set");
+
+            let recorded = app.launch(&mut params, inverse)?;
+
+            // `buffers` keeps the vulkano buffers alive until vulkano has seen the primary command
+            // buffer finish. Raw buffers are the caller's responsibility.
+            recorded.assume_completed();
+
+            let launch_buffers = [
+                (params.buffer, false),
+                (params.temp_buffer, false),
+                (params.input_buffer, false),
+                (params.output_buffer, false),
+                (params.kernel, true),
+            ];
+
+            buffers.extend(
+                launch_buffers
+                    .into_iter()
+                    .filter_map(|(b, read_only)| b.map(|b| (b, read_only))),
+            );
+
+            builder.build().map_err(|_| error::Error::FailedToEndCommandBuffer)?
+        };
+
+        Ok(Self {
+            inner,
+            _alloc: alloc.into_alloc(),
+            inheritance_info,
+            buffers: buffers
+                .into_iter()
+                .filter_map(|(b, read_only)| Self::buffer_access(&b, read_only))
+                .collect(),
+            in_use: AtomicBool::new(false),
+        })
+    }
+
+    fn allocate(device: &Arc<Device>, queue_family_index: u32) -> error::Result<impl CommandPoolBuilderAlloc<Alloc = StandardCommandPoolAlloc>> {
+        device
+            .with_standard_command_pool(queue_family_index, |pool| {
+                pool.allocate(CommandBufferLevel::Secondary, 1)
+                    .map(|mut iter| iter.next())
+            })
+            .ok()
+            .and_then(|r| r.ok())
+            .flatten()
+            .ok_or(error::Error::FailedToAllocateCommandBuffers)
+    }
+
+    fn buffer_access(buffer: &Buffer, read_only: bool) -> Option<(Arc<dyn BufferAccess>, Range<DeviceSize>, PipelineMemoryAccess)> {
+        let buffer = buffer.as_buffer_access()?.clone();
+        let range = 0..buffer.size();
+
+        let access = PipelineMemoryAccess {
+            stages: PipelineStages {
+                compute_shader: true,
+                ..PipelineStages::empty()
+            },
+            access: AccessFlags {
+                shader_read: true,
+                shader_write: !read_only,
+                ..AccessFlags::empty()
+            },
+            exclusive: !read_only,
+        };
+
+        Some((buffer, range, access))
+    }
+}
+
+unsafe impl DeviceOwned for FftCommandBuffer {
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+unsafe impl SecondaryCommandBuffer for FftCommandBuffer {
+    fn inner(&self) -> &UnsafeCommandBuffer {
+        &self.inner
+    }
+
+    fn lock_record(&self) -> Result<(), CommandBufferExecError> {
+        if self.in_use.swap(true, Ordering::SeqCst) {
+            return Err(CommandBufferExecError::ExclusiveAlreadyInUse);
+        }
+
+        Ok(())
+    }
+
+    unsafe fn unlock(&self) {
+        debug_assert!(self.in_use.load(Ordering::SeqCst));
+        self.in_use.store(false, Ordering::SeqCst);
+    }
+
+    fn inheritance_info(&self) -> &CommandBufferInheritanceInfo {
+        &self.inheritance_info
+    }
+
+    fn num_buffers(&self) -> usize {
+        self.buffers.len()
+    }
+
+    fn buffer(&self, index: usize) -> Option<(&Arc<dyn BufferAccess>, Range<DeviceSize>, PipelineMemoryAccess)> {
+        self.buffers
+            .get(index)
+            .map(|(buffer, range, access)| (buffer, range.clone(), *access))
+    }
+
+    fn num_images(&self) -> usize {
+        0
+    }
+
+    fn image(
+        &self,
+        _index: usize,
+    ) -> Option<(&Arc<dyn ImageAccess>, &ImageSubresourceRange, PipelineMemoryAccess, ImageLayout, ImageLayout)> {
+        None
+    }
+}
@@ -1,18 +1,22 @@
-use std::sync::Arc;
-
+use ash::vk;
 use derive_more::{Display, Error};
 use std::pin::Pin;
+#[cfg(feature = "vulkano")]
+use std::sync::Arc;
+#[cfg(feature = "vulkano")]
 use vulkano::{
-    buffer::BufferAccess,
+    buffer::{BufferAccess, TypedBufferAccess},
     command_buffer::pool::UnsafeCommandPool,
-    device::{Device, Queue},
+    device::{Device, DeviceOwned, Queue},
     device::physical::PhysicalDevice,
-    sync::Fence,
+    sync::{Fence, FenceCreateInfo},
     SynchronizedVulkanObject, VulkanObject,
 };
-use ash::vk::Handle;
 use std::ptr::addr_of_mut;
 
+#[cfg(feature = "vulkano")]
+use crate::element::Element;
+
 #[derive(Display, Debug, Error)]
 pub enum BuildError {
     NoPhysicalDevice,
@@ -21,17 +25,29 @@ pub enum BuildError {
     NoFence,
     NoCommandPool,
     NoBuffer,
+    FailedToCreateFence,
+    /// The element types of the buffers don't agree on a precision
+    PrecisionMismatch,
+    /// Profiling needs the timestamp period of the physical device
+    NoTimestampPeriod,
+    /// Profiling needs a queue family that writes timestamps
+    NoTimestampSupport,
+    /// The tuning file couldn't be read
+    #[cfg(feature = "autotune")]
+    TuningFile(crate::autotune::TuningError),
 }
 
-pub struct ConfigBuilder<'a> {
+#[derive(Clone)]
+pub struct ConfigBuilder {
     fft_dim: u64,
     size: [u64; 3usize],
 
-    physical_device: Option<Arc<PhysicalDevice>>,
-    device: Option<Arc<Device>>,
-    queue: Option<Arc<Queue>>,
-    fence: Option<&'a Fence>,
-    command_pool: Option<Arc<UnsafeCommandPool>>,
+    physical_device: Option<vk::PhysicalDevice>,
+    device: Option<ash::Device>,
+    queue: Option<vk::Queue>,
+    fence: Option<vk::Fence>,
+    command_pool: Option<vk::CommandPool>,
+    keep_alive: KeepAlive,
     buffer: Option<BufferDesc>,
     input_buffer: Option<BufferDesc>,
     output_buffer: Option<BufferDesc>,
@@ -41,20 +57,36 @@ pub struct ConfigBuilder<'a> {
     zero_padding: [bool; 3usize],
     zeropad_left: [u64; 3usize],
     zeropad_right: [u64; 3usize],
+    omit_dimension: [bool; 3usize],
+    buffer_stride: [u64; 3usize],
+    input_buffer_stride: [u64; 3usize],
+    output_buffer_stride: [u64; 3usize],
     kernel_convolution: bool,
     convolution: bool,
     r2c: bool,
     coordinate_features: u64,
     disable_reorder_four_step: bool,
     batch_count: Option<u64>,
-    precision: Precision,
+    precision: Option<Precision>,
     use_lut: bool,
     symmetric_kernel: bool,
     input_formatted: Option<bool>,
     output_formatted: Option<bool>,
+    inverse_return_to_input_buffer: bool,
+    dct: Option<Dct>,
+    profile: bool,
+    timestamp_period: Option<f32>,
+    timestamp_valid_bits: Option<u32>,
+    debug_info: bool,
+    algorithm: AlgorithmOptions,
+    tuning: Option<TuningOptions>,
+    #[cfg(feature = "autotune")]
+    tuning_file: Option<std::path::PathBuf>,
+    #[cfg(feature = "wisdom")]
+    record_wisdom: bool,
 }
 
-impl<'a> ConfigBuilder<'a> {
+impl ConfigBuilder {
     pub fn new() -> Self {
         Self {
             fft_dim: 1,
@@ -64,10 +96,15 @@ impl<'a> ConfigBuilder<'a> {
             queue: None,
             fence: None,
             command_pool: None,
+            keep_alive: KeepAlive::default(),
             normalize: false,
             zero_padding: [false, false, false],
             zeropad_left: [0, 0, 0],
             zeropad_right: [0, 0, 0],
+            omit_dimension: [false, false, false],
+            buffer_stride: [0, 0, 0],
+            input_buffer_stride: [0, 0, 0],
+            output_buffer_stride: [0, 0, 0],
             kernel_convolution: false,
             r2c: false,
             coordinate_features: 1,
@@ -77,12 +114,24 @@ impl<'a> ConfigBuilder<'a> {
             input_buffer: None,
             output_buffer: None,
             batch_count: None,
-            precision: Precision::Single,
+            precision: None,
             convolution: false,
             use_lut: false,
             symmetric_kernel: false,
             input_formatted: None,
             output_formatted: None,
+            inverse_return_to_input_buffer: false,
+            dct: None,
+            profile: false,
+            timestamp_period: None,
+            timestamp_valid_bits: None,
+            debug_info: false,
+            algorithm: AlgorithmOptions::new(),
+            tuning: None,
+            #[cfg(feature = "autotune")]
+            tuning_file: None,
+            #[cfg(feature = "wisdom")]
+            record_wisdom: false,
             kernel: None,
         }
     }
@@ -104,31 +153,87 @@ impl<'a> ConfigBuilder<'a> {
         self
     }
 
-    pub fn physical_device(mut self, physical_device: Arc<PhysicalDevice>) -> Self {
+    /// # Safety
+    ///
+    /// `physical_device` must be a valid handle that outlives the `App` built from this config.
+    pub unsafe fn raw_physical_device(mut self, physical_device: vk::PhysicalDevice) -> Self {
         self.physical_device = Some(physical_device);
         self
     }
 
-    pub fn device(mut self, device: Arc<Device>) -> Self {
-        self.device = Some(device);
+    /// # Safety
+    ///
+    /// `device` must be created from the physical device passed to
+    /// [`raw_physical_device`](Self::raw_physical_device) and outlive the `App` built from this config.
+    pub unsafe fn raw_device(mut self, device: &ash::Device) -> Self {
+        self.device = Some(device.clone());
         self
     }
 
-    pub fn queue(mut self, queue: Arc<Queue>) -> Self {
+    /// # Safety
+    ///
+    /// `queue` must be a compute capable queue of the device and outlive the `App` built from this
+    /// config. VkFFT submits to it while initializing, so it must not be used concurrently.
+    pub unsafe fn raw_queue(mut self, queue: vk::Queue) -> Self {
         self.queue = Some(queue);
         self
     }
 
-    pub fn command_pool(mut self, command_pool: Arc<UnsafeCommandPool>) -> Self {
+    /// # Safety
+    ///
+    /// `command_pool` must belong to the queue family of the queue and outlive the `App` built from
+    /// this config.
+    pub unsafe fn raw_command_pool(mut self, command_pool: vk::CommandPool) -> Self {
         self.command_pool = Some(command_pool);
         self
     }
 
-    pub fn fence(mut self, fence: &'a Fence) -> Self {
+    /// # Safety
+    ///
+    /// `fence` must be an unsignaled fence of the device and outlive the `App` built from this config.
+    pub unsafe fn raw_fence(mut self, fence: vk::Fence) -> Self {
         self.fence = Some(fence);
         self
     }
 
+    #[cfg(feature = "vulkano")]
+    pub fn physical_device(mut self, physical_device: Arc<PhysicalDevice>) -> Self {
+        self.physical_device = Some(physical_device.internal_object());
+        self.keep_alive.physical_device = Some(physical_device);
+        self
+    }
+
+    #[cfg(feature = "vulkano")]
+    pub fn device(mut self, device: Arc<Device>) -> Self {
+        let raw = unsafe { ash::Device::load(&device.instance().fns().v1_0, device.internal_object()) };
+
+        self.device = Some(raw);
+        self.keep_alive.device = Some(device);
+        self
+    }
+
+    #[cfg(feature = "vulkano")]
+    pub fn queue(mut self, queue: Arc<Queue>) -> Self {
+        self.queue = Some(*queue.internal_object_guard());
+        self.keep_alive.queue = Some(queue);
+        self
+    }
+
+    #[cfg(feature = "vulkano")]
+    pub fn command_pool(mut self, command_pool: Arc<UnsafeCommandPool>) -> Self {
+        self.command_pool = Some(command_pool.internal_object());
+        self.keep_alive.command_pool = Some(command_pool);
+        self
+    }
+
+    /// If no fence is given, [`build`](Self::build) creates one on the vulkano device.
+    #[cfg(feature = "vulkano")]
+    pub fn fence(mut self, fence: Arc<Fence>) -> Self {
+        self.fence = Some(fence.internal_object());
+        self.keep_alive.fence = Some(fence);
+        self
+    }
+
     pub fn buffer<B>(mut self, buffer: B) -> Self
         where
             B: Into<BufferDesc>,
@@ -194,11 +299,85 @@ impl<'a> ConfigBuilder<'a> {
         self
     }
 
+    /// Performs a real-to-real DCT of the given type instead of an FFT.
+    pub fn dct(mut self, dct: Dct) -> Self {
+        self.dct = Some(dct);
+        self
+    }
+
+    /// Writes timestamps around the dispatches of each axis of every launch, which
+    /// [`App::profile`](crate::app::App::profile) reads back once the launch has finished.
+    pub fn profile(mut self) -> Self {
+        self.profile = true;
+        self
+    }
+
+    /// Nanoseconds per timestamp tick, `timestampPeriod` in the limits of the physical device. Taken
+    /// from the physical device passed to [`physical_device`](Self::physical_device) if not given.
+    pub fn timestamp_period(mut self, timestamp_period: f32) -> Self {
+        self.timestamp_period = Some(timestamp_period);
+        self
+    }
+
+    /// Bits of the timestamps the queue writes, `timestampValidBits` of its queue family. Taken
+    /// from the queue passed to [`queue`](Self::queue) if not given, and all 64 bits are assumed
+    /// valid without either.
+    pub fn timestamp_valid_bits(mut self, timestamp_valid_bits: u32) -> Self {
+        self.timestamp_valid_bits = Some(timestamp_valid_bits);
+        self
+    }
+
+    /// Keeps the shaders VkFFT generates and the buffers they use, see
+    /// [`App::debug_info`](crate::app::App::debug_info).
+    pub fn debug_info(mut self) -> Self {
+        self.debug_info = true;
+        self
+    }
+
+    /// Overrides the thresholds VkFFT picks per vendor for Bluestein's and Rader's algorithms.
+    pub fn algorithm(mut self, algorithm: AlgorithmOptions) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Overrides the performance parameters VkFFT picks per vendor.
+    pub fn tuning(mut self, tuning: TuningOptions) -> Self {
+        self.tuning = Some(tuning);
+        self
+    }
+
+    /// Takes the tuning from the entry of a [`TuningFile`](crate::autotune::TuningFile) for the
+    /// device and shape, unless given with [`tuning`](Self::tuning). A missing file or entry
+    /// keeps VkFFT's defaults.
+    #[cfg(feature = "autotune")]
+    pub fn tuning_file<P: AsRef<std::path::Path>>(mut self, path: P) -> Self {
+        self.tuning_file = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Records the tuning of the plan into [`Wisdom::global`](crate::wisdom::Wisdom::global) when
+    /// an [`App`](crate::app::App) is created from the config, so later configs of the same plan
+    /// take it from there. Tunings from a tuning file or the wisdom itself are always recorded.
+    #[cfg(feature = "wisdom")]
+    pub fn record_wisdom(mut self) -> Self {
+        self.record_wisdom = true;
+        self
+    }
+
+    /// Uses lookup tables instead of computing the twiddle factors, whatever the tuning options
+    /// say. VkFFT otherwise only uses them in double precision and on Intel GPUs.
     pub fn use_lut(mut self) -> Self {
         self.use_lut = true;
         self
     }
 
+    /// Overrides the precision inferred from the buffers passed with [`Buffer::typed`]. Defaults to
+    /// [`Precision::Single`] if neither is given.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
     pub fn coordinate_features(mut self, coordinate_features: u64) -> Self {
         self.coordinate_features = coordinate_features;
         self
@@ -257,6 +436,39 @@ impl<'a> ConfigBuilder<'a> {
         self
     }
 
+    /// Skips the transform along the given axes, which are then batched over.
+    pub fn omit_dimension<const N: usize>(mut self, omit_dimension: &[bool; N]) -> Self {
+        assert!(N <= 3);
+
+        self.omit_dimension[..N].copy_from_slice(omit_dimension);
+        self
+    }
+
+    /// Elements between consecutive y rows, z planes and batches of `buffer`. Zero keeps the packed
+    /// default.
+    pub fn buffer_stride<const N: usize>(mut self, buffer_stride: &[u64; N]) -> Self {
+        assert!(N <= 3);
+
+        self.buffer_stride[..N].copy_from_slice(buffer_stride);
+        self
+    }
+
+    /// Like [`buffer_stride`](Self::buffer_stride) for `input_buffer`. Requires `input_formatted`.
+    pub fn input_buffer_stride<const N: usize>(mut self, input_buffer_stride: &[u64; N]) -> Self {
+        assert!(N <= 3);
+
+        self.input_buffer_stride[..N].copy_from_slice(input_buffer_stride);
+        self
+    }
+
+    /// Like [`buffer_stride`](Self::buffer_stride) for `output_buffer`. Requires `output_formatted`.
+    pub fn output_buffer_stride<const N: usize>(mut self, output_buffer_stride: &[u64; N]) -> Self {
+        assert!(N <= 3);
+
+        self.output_buffer_stride[..N].copy_from_slice(output_buffer_stride);
+        self
+    }
+
     pub fn batch_count(mut self, batch_count: u64) -> Self {
         self.batch_count = Some(batch_count);
         self
@@ -272,7 +484,98 @@ impl<'a> ConfigBuilder<'a> {
         self
     }
 
-    pub fn build(self) -> Result<Config<'a>, BuildError> {
+    pub fn inverse_return_to_input_buffer(mut self) -> Self {
+        self.inverse_return_to_input_buffer = true;
+        self
+    }
+
+    pub(crate) fn batches(&self) -> u64 {
+        self.batch_count.unwrap_or(1)
+    }
+
+    /// The config of a later stage of an [`NdApp`](crate::nd::NdApp), which transforms the complex
+    /// data the first stage left in `buffer` in place.
+    pub(crate) fn complex_stage(mut self) -> Result<Self, ConfigError> {
+        let in_buffer = self.output_formatted != Some(true) && !matches!(self.precision, Some(Precision::HalfMemory));
+
+        if !in_buffer || self.convolution || self.kernel_convolution || self.zero_padding.contains(&true) || self.dct.is_some() {
+            return Err(ConfigError::InvalidConfig);
+        }
+
+        self.r2c = false;
+        self.input_buffer = None;
+        self.input_formatted = None;
+        self.input_buffer_stride = [0, 0, 0];
+        self.inverse_return_to_input_buffer = false;
+        Ok(self)
+    }
+
+    /// Infers the precision from the element types of the typed buffers. Half precision input and
+    /// output buffers next to single precision buffers mean [`Precision::HalfMemory`].
+    fn infer_precision(&self) -> Result<Precision, BuildError> {
+        fn common<'a>(buffers: impl IntoIterator<Item = &'a Option<BufferDesc>>) -> Result<Option<Precision>, BuildError> {
+            let mut precisions = buffers
+                .into_iter()
+                .filter_map(|b| b.as_ref()?.as_buffer()?.precision());
+
+            let first = precisions.next();
+
+            match first {
+                Some(p) if precisions.any(|q| q != p) => Err(BuildError::PrecisionMismatch),
+                _ => Ok(first),
+            }
+        }
+
+        let compute = common([&self.buffer, &self.temp_buffer, &self.kernel])?;
+        let io = common([&self.input_buffer, &self.output_buffer])?;
+
+        match (compute, io) {
+            (None, None) => Ok(Precision::Single),
+            (Some(Precision::Single), Some(Precision::Half)) => Ok(Precision::HalfMemory),
+            (Some(p), None) | (None, Some(p)) => Ok(p),
+            (Some(p), Some(q)) if p == q => Ok(p),
+            _ => Err(BuildError::PrecisionMismatch),
+        }
+    }
+
+    /// The key of the plan in a tuning file, `None` without a vulkano physical device.
+    #[cfg(feature = "autotune")]
+    pub(crate) fn tuning_key(&self) -> Result<Option<crate::autotune::TuningKey>, BuildError> {
+        let precision = match self.precision {
+            Some(v) => v,
+            None => self.infer_precision()?,
+        };
+
+        Ok(self.keep_alive.physical_device.as_ref().map(|p| crate::autotune::TuningKey {
+            device: crate::autotune::device_id(p),
+            size: self.size[..self.fft_dim as usize].to_vec(),
+            precision,
+            r2c: self.r2c,
+            batch_count: self.batches(),
+        }))
+    }
+
+    #[cfg_attr(not(feature = "vulkano"), allow(unused_mut))]
+    pub fn build(mut self) -> Result<Config, BuildError> {
+        #[cfg(feature = "autotune")]
+        if let (None, Some(path)) = (self.tuning, &self.tuning_file) {
+            let file = crate::autotune::TuningFile::load(path).map_err(BuildError::TuningFile)?;
+
+            if let Some(key) = self.tuning_key()? {
+                self.tuning = file.get(&key).map(|e| e.tuning);
+
+                #[cfg(feature = "wisdom")]
+                {
+                    self.record_wisdom |= self.tuning.is_some();
+                }
+            }
+        }
+
+        let precision = match self.precision {
+            Some(v) => v,
+            None => self.infer_precision()?,
+        };
+
         let physical_device = match self.physical_device {
             Some(v) => v,
             None => return Err(BuildError::NoPhysicalDevice),
@@ -288,6 +591,17 @@ impl<'a> ConfigBuilder<'a> {
             None => return Err(BuildError::NoQueue),
         };
 
+        #[cfg(feature = "vulkano")]
+        if self.fence.is_none() {
+            if let Some(device) = &self.keep_alive.device {
+                let fence = Fence::new(device.clone(), FenceCreateInfo::default())
+                    .map_err(|_| BuildError::FailedToCreateFence)?;
+
+                self.fence = Some(fence.internal_object());
+                self.keep_alive.fence = Some(Arc::new(fence));
+            }
+        }
+
         let fence = match self.fence {
             Some(v) => v,
             None => return Err(BuildError::NoFence),
@@ -298,7 +612,31 @@ impl<'a> ConfigBuilder<'a> {
             None => return Err(BuildError::NoCommandPool),
         };
 
-        Ok(Config {
+        #[cfg(feature = "vulkano")]
+        if self.timestamp_period.is_none() {
+            if let Some(physical_device) = &self.keep_alive.physical_device {
+                self.timestamp_period = Some(physical_device.properties().timestamp_period);
+            }
+        }
+
+        if self.profile && self.timestamp_period.is_none() {
+            return Err(BuildError::NoTimestampPeriod);
+        }
+
+        #[cfg(feature = "vulkano")]
+        if self.timestamp_valid_bits.is_none() {
+            if let Some(queue) = &self.keep_alive.queue {
+                let families = queue.device().physical_device().queue_family_properties();
+                let family = &families[queue.queue_family_index() as usize];
+                self.timestamp_valid_bits = Some(family.timestamp_valid_bits.unwrap_or(0));
+            }
+        }
+
+        if self.profile && self.timestamp_valid_bits == Some(0) {
+            return Err(BuildError::NoTimestampSupport);
+        }
+
+        let config = Config {
             fft_dim: self.fft_dim,
             size: self.size,
             physical_device,
@@ -306,30 +644,55 @@ impl<'a> ConfigBuilder<'a> {
             queue,
             fence,
             command_pool,
+            keep_alive: self.keep_alive,
             normalize: self.normalize,
             zero_padding: self.zero_padding,
             zeropad_left: self.zeropad_left,
             zeropad_right: self.zeropad_right,
+            omit_dimension: self.omit_dimension,
+            buffer_stride: self.buffer_stride,
+            input_buffer_stride: self.input_buffer_stride,
+            output_buffer_stride: self.output_buffer_stride,
             kernel_convolution: self.kernel_convolution,
             r2c: self.r2c,
             coordinate_features: self.coordinate_features,
             disable_reorder_four_step: self.disable_reorder_four_step,
             buffer: self.buffer,
             batch_count: self.batch_count,
-            precision: self.precision,
+            precision,
             convolution: self.convolution,
             use_lut: self.use_lut,
             symmetric_kernel: self.symmetric_kernel,
             input_formatted: self.input_formatted,
             output_formatted: self.output_formatted,
+            inverse_return_to_input_buffer: self.inverse_return_to_input_buffer,
+            dct: self.dct,
+            profile: self.profile,
+            timestamp_period: self.timestamp_period,
+            timestamp_valid_bits: self.timestamp_valid_bits,
+            debug_info: self.debug_info,
+            algorithm: self.algorithm,
+            tuning: self.tuning.unwrap_or_default(),
+            #[cfg(feature = "wisdom")]
+            record_wisdom: self.record_wisdom,
             kernel: self.kernel,
             temp_buffer: self.temp_buffer,
             input_buffer: self.input_buffer,
             output_buffer: self.output_buffer,
-        })
+        };
+
+        #[cfg(feature = "wisdom")]
+        let config = match self.tuning {
+            Some(_) => config,
+            None => crate::wisdom::Wisdom::global().tune(config),
+        };
+
+        Ok(config)
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "autotune", derive(serde::Serialize, serde::Deserialize))]
 pub enum Precision {
     /// Perform calculations in single precision (32-bit)
     Single,
@@ -342,17 +705,250 @@ pub enum Precision {
     HalfMemory,
 }
 
+/// Type of the real-to-real DCT, with the unnormalized definitions of FFTW's `REDFT00`, `REDFT10`,
+/// `REDFT01` and `REDFT11`. The inverse of a DCT-II is a DCT-III and vice versa.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dct {
+    I = 1,
+    II = 2,
+    III = 3,
+    IV = 4,
+}
+
+/// Thresholds of Bluestein's and Rader's algorithms for sizes with large prime factors. Unset
+/// options keep the values VkFFT picks for the vendor and precision.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlgorithmOptions {
+    fix_max_radix_bluestein: Option<u64>,
+    force_bluestein_sequence_size: Option<u64>,
+    prime_sizes: Vec<u64>,
+    padded_sizes: Vec<u64>,
+    fix_min_rader_prime_mult: Option<u64>,
+    fix_max_rader_prime_mult: Option<u64>,
+    fix_min_rader_prime_fft: Option<u64>,
+    fix_max_rader_prime_fft: Option<u64>,
+}
+
+impl AlgorithmOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pads sequences in Bluestein's algorithm to sizes made of primes up to this one, between 2
+    /// and 13. Replaces the padding pattern.
+    pub fn fix_max_radix_bluestein(mut self, fix_max_radix_bluestein: u64) -> Self {
+        self.fix_max_radix_bluestein = Some(fix_max_radix_bluestein);
+        self
+    }
+
+    /// Pads sequences in Bluestein's algorithm to this size, which must be at least `2 * N - 1` and
+    /// made of primes up to 13.
+    pub fn force_bluestein_sequence_size(mut self, force_bluestein_sequence_size: u64) -> Self {
+        self.force_bluestein_sequence_size = Some(force_bluestein_sequence_size);
+        self
+    }
+
+    /// Pads sequences in Bluestein's algorithm from `prime_sizes[i]` up to `prime_sizes[i + 1]`
+    /// to `padded_sizes[i]`. Sequences past the last prime size use the default scheme.
+    pub fn custom_bluestein_padding_pattern(mut self, prime_sizes: &[u64], padded_sizes: &[u64]) -> Self {
+        assert_eq!(prime_sizes.len(), padded_sizes.len());
+
+        self.prime_sizes = prime_sizes.to_vec();
+        self.padded_sizes = padded_sizes.to_vec();
+        self
+    }
+
+    /// Uses the direct multiplication version of Rader's algorithm for radix primes from this one
+    /// on, 17 by default. Below 13 it replaces VkFFT's own kernels for small primes.
+    pub fn fix_min_rader_prime_mult(mut self, fix_min_rader_prime_mult: u64) -> Self {
+        self.fix_min_rader_prime_mult = Some(fix_min_rader_prime_mult);
+        self
+    }
+
+    /// Stops using the direct multiplication version of Rader's algorithm from this prime on.
+    pub fn fix_max_rader_prime_mult(mut self, fix_max_rader_prime_mult: u64) -> Self {
+        self.fix_max_rader_prime_mult = Some(fix_max_rader_prime_mult);
+        self
+    }
+
+    /// Uses the FFT convolution version of Rader's algorithm for radix primes from this one on. Must
+    /// be at least `fix_min_rader_prime_mult`.
+    pub fn fix_min_rader_prime_fft(mut self, fix_min_rader_prime_fft: u64) -> Self {
+        self.fix_min_rader_prime_fft = Some(fix_min_rader_prime_fft);
+        self
+    }
+
+    /// Switches to Bluestein's algorithm for radix primes from this one on, 16384 by default.
+    /// Primes that don't fit into shared memory switch earlier.
+    pub fn fix_max_rader_prime_fft(mut self, fix_max_rader_prime_fft: u64) -> Self {
+        self.fix_max_rader_prime_fft = Some(fix_max_rader_prime_fft);
+        self
+    }
+}
+
+/// Performance parameters VkFFT otherwise picks from the vendor of the device and the precision.
+/// `None` keeps VkFFT's choice. They only change how fast a plan runs, not its result.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "autotune", derive(serde::Serialize, serde::Deserialize))]
+pub struct TuningOptions {
+    /// How many times the register file may extend shared memory, e.g. 4 to emulate 128KB of
+    /// shared memory with a 256KB register file next to 32KB of shared memory. VkFFT uses 4 on
+    /// NVIDIA and 1 or 2 on AMD, depending on the shared memory size.
+    pub register_boost: Option<u64>,
+
+    /// Also extend shared memory with registers for lengths that aren't a power of two.
+    pub register_boost_non_pow2: bool,
+
+    /// Like `register_boost`, for sequences longer than 2^14 that need the four-step algorithm.
+    pub register_boost_4_step: Option<u64>,
+
+    /// Divides the coalesced memory size by this factor on strided axes, which fits longer
+    /// sequences into one pass. -1 turns it off. VkFFT uses 2 with Bluestein's algorithm and -1
+    /// for DCTs.
+    pub bandwidth_boost: Option<i64>,
+
+    /// Bytes of a coalesced memory transaction, 32 on NVIDIA and AMD and 64 on Intel, twice that
+    /// in half precision.
+    pub coalesced_memory: Option<u64>,
+
+    /// Threads per workgroup to aim for, 128 by default.
+    pub aim_threads: Option<u64>,
+
+    /// Banks of shared memory, 32 by default.
+    pub num_shared_banks: Option<u64>,
+
+    /// Length from which the four-step algorithm uses three passes instead of two, which can avoid
+    /// TLB misses. At least 131072.
+    pub swap_to_3_stage_4_step: Option<u64>,
+
+    /// Use lookup tables instead of computing the twiddle factors. VkFFT uses them in double
+    /// precision and on Intel GPUs. [`ConfigBuilder::use_lut`] turns them on regardless.
+    pub use_lut: Option<bool>,
+
+    /// Like `use_lut`, for the twiddle factors between the passes of the four-step algorithm.
+    /// `Some(true)` also turns on `use_lut`, `None` follows whether the plan ends up using it.
+    pub use_lut_4_step: Option<bool>,
+}
+
+/// A device buffer handed to VkFFT, either at plan creation or at launch.
+#[derive(Clone)]
+pub struct Buffer {
+    handle: vk::Buffer,
+    size: u64,
+    precision: Option<Precision>,
+    #[cfg(feature = "vulkano")]
+    access: Option<Arc<dyn BufferAccess>>,
+}
+
+impl Buffer {
+    /// # Safety
+    ///
+    /// `buffer` must be a valid buffer of at least `size` bytes, created with storage buffer usage,
+    /// that stays alive for as long as VkFFT may access it.
+    pub unsafe fn from_raw(buffer: vk::Buffer, size: u64) -> Self {
+        Self {
+            handle: buffer,
+            size,
+            precision: None,
+            #[cfg(feature = "vulkano")]
+            access: None,
+        }
+    }
+
+    /// Wraps a vulkano buffer of [`Element`]s, so [`ConfigBuilder`] can infer the precision from
+    /// the element type. Converting the buffer with `into` does the same for the element types of
+    /// this crate, e.g. for a `CpuAccessibleBuffer<[Complex<f32>]>`; this also covers other
+    /// [`Element`] implementations.
+    #[cfg(feature = "vulkano")]
+    pub fn typed<B, T>(buffer: Arc<B>) -> Self
+        where
+            B: 'static + TypedBufferAccess<Content = [T]>,
+            T: Element,
+    {
+        Self {
+            precision: Some(T::PRECISION),
+            ..Self::from(buffer)
+        }
+    }
+
+    pub fn handle(&self) -> vk::Buffer {
+        self.handle
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The precision of the elements, if the element type of the buffer is known, see
+    /// [`typed`](Self::typed).
+    pub fn precision(&self) -> Option<Precision> {
+        self.precision
+    }
+
+    /// The device the buffer belongs to, if known. Buffers created with
+    /// [`from_raw`](Self::from_raw) don't carry their device.
+    pub fn device(&self) -> Option<vk::Device> {
+        #[cfg(feature = "vulkano")]
+        if let Some(access) = &self.access {
+            return Some(access.device().internal_object());
+        }
+
+        None
+    }
+
+    #[cfg(feature = "vulkano")]
+    pub fn as_buffer_access(&self) -> Option<&Arc<dyn BufferAccess>> {
+        self.access.as_ref()
+    }
+}
+
+#[cfg(feature = "vulkano")]
+impl From<Arc<dyn BufferAccess>> for Buffer {
+    fn from(value: Arc<dyn BufferAccess>) -> Self {
+        Self {
+            handle: value.inner().buffer.internal_object(),
+            size: value.size(),
+            precision: None,
+            access: Some(value),
+        }
+    }
+}
+
+/// Infers the precision from the contents of the buffer, e.g. `[f64]` or `[Complex<f32>]`.
+#[cfg(feature = "vulkano")]
+impl<T> From<Arc<T>> for Buffer
+    where
+        T: 'static + TypedBufferAccess,
+        T::Content: 'static,
+{
+    fn from(value: Arc<T>) -> Self {
+        Self {
+            precision: crate::element::content_precision::<T::Content>(),
+            ..Self::from(value as Arc<dyn BufferAccess>)
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum BufferDesc {
-    Buffer(Arc<dyn BufferAccess>),
+    Buffer(Buffer),
     BufferSize(usize),
 }
 
+impl From<Buffer> for BufferDesc {
+    fn from(value: Buffer) -> Self {
+        Self::Buffer(value)
+    }
+}
+
+#[cfg(feature = "vulkano")]
 impl<T> From<Arc<T>> for BufferDesc
     where
-        T: 'static + BufferAccess,
+        T: 'static + TypedBufferAccess,
+        T::Content: 'static,
 {
     fn from(value: Arc<T>) -> Self {
-        Self::Buffer(value as Arc<dyn BufferAccess>)
+        Self::Buffer(value.into())
     }
 }
 
@@ -370,7 +966,7 @@ impl BufferDesc {
         }
     }
 
-    pub fn as_buffer(&self) -> Option<&Arc<dyn BufferAccess>> {
+    pub fn as_buffer(&self) -> Option<&Buffer> {
         match self {
             Self::Buffer(b) => Some(b),
             Self::BufferSize(_) => None,
@@ -385,15 +981,16 @@ impl BufferDesc {
     }
 }
 
-pub struct Config<'a> {
+pub struct Config {
     pub fft_dim: u64,
     pub size: [u64; 3usize],
 
-    pub physical_device: Arc<PhysicalDevice>,
-    pub device: Arc<Device>,
-    pub queue: Arc<Queue>,
-    pub fence: &'a Fence,
-    pub command_pool: Arc<UnsafeCommandPool>,
+    pub(crate) physical_device: vk::PhysicalDevice,
+    pub(crate) device: ash::Device,
+    pub(crate) queue: vk::Queue,
+    pub(crate) fence: vk::Fence,
+    pub(crate) command_pool: vk::CommandPool,
+    pub(crate) keep_alive: KeepAlive,
 
     pub buffer: Option<BufferDesc>,
     pub input_buffer: Option<BufferDesc>,
@@ -413,6 +1010,18 @@ pub struct Config<'a> {
     /// Specify end boundary of zero block in the system for each axis
     pub zeropad_right: [u64; 3usize],
 
+    /// Disable the transform along each axis
+    pub omit_dimension: [bool; 3usize],
+
+    /// Elements between consecutive y rows, z planes and batches of `buffer`, zero for packed
+    pub buffer_stride: [u64; 3usize],
+
+    /// Elements between consecutive y rows, z planes and batches of `input_buffer`, zero for packed
+    pub input_buffer_stride: [u64; 3usize],
+
+    /// Elements between consecutive y rows, z planes and batches of `output_buffer`, zero for packed
+    pub output_buffer_stride: [u64; 3usize],
+
     /// Specify if this application is used to create kernel for convolution, so it has the same properties
     pub kernel_convolution: bool,
 
@@ -448,6 +1057,34 @@ pub struct Config<'a> {
     /// For example if it is not padded for R2C if out-of-place mode is selected
     /// (only if numberBatches==1 and numberKernels==1)
     pub output_formatted: Option<bool>,
+
+    /// Return data to the input buffer in the inverse transform. Requires `input_formatted`
+    pub inverse_return_to_input_buffer: bool,
+
+    /// Perform a real-to-real DCT of this type instead of an FFT
+    pub dct: Option<Dct>,
+
+    /// Write timestamps around the dispatches of each axis of every launch
+    pub profile: bool,
+
+    /// Nanoseconds per timestamp tick of the physical device
+    pub timestamp_period: Option<f32>,
+
+    /// Bits of the timestamps the queue writes, all 64 if `None`
+    pub timestamp_valid_bits: Option<u32>,
+
+    /// Keep the generated shader code and the buffers each shader uses
+    pub debug_info: bool,
+
+    /// Thresholds of Bluestein's and Rader's algorithms
+    pub algorithm: AlgorithmOptions,
+
+    /// Performance parameters
+    pub tuning: TuningOptions,
+
+    /// Record the tuning into the global wisdom when the plan is created
+    #[cfg(feature = "wisdom")]
+    pub record_wisdom: bool,
 }
 
 #[derive(Display, Debug, Error)]
@@ -455,47 +1092,88 @@ pub enum ConfigError {
     InvalidConfig,
 }
 
+/// Owners of the objects whose raw handles were given to VkFFT. Empty for handles passed through
+/// the `raw_*` methods.
+#[derive(Clone, Default)]
 pub(crate) struct KeepAlive {
-    pub device: Arc<Device>,
-    pub queue: Arc<Queue>,
-    pub command_pool: Arc<UnsafeCommandPool>,
-
-    pub buffer: Option<Arc<dyn BufferAccess>>,
-    pub input_buffer: Option<Arc<dyn BufferAccess>>,
-    pub output_buffer: Option<Arc<dyn BufferAccess>>,
-    pub temp_buffer: Option<Arc<dyn BufferAccess>>,
-    pub kernel: Option<Arc<dyn BufferAccess>>,
+    #[cfg(feature = "vulkano")]
+    pub physical_device: Option<Arc<PhysicalDevice>>,
+    #[cfg(feature = "vulkano")]
+    pub device: Option<Arc<Device>>,
+    #[cfg(feature = "vulkano")]
+    pub queue: Option<Arc<Queue>>,
+    #[cfg(feature = "vulkano")]
+    pub command_pool: Option<Arc<UnsafeCommandPool>>,
+    #[cfg(feature = "vulkano")]
+    pub fence: Option<Arc<Fence>>,
+
+    pub buffer: Option<Buffer>,
+    pub input_buffer: Option<Buffer>,
+    pub output_buffer: Option<Buffer>,
+    pub temp_buffer: Option<Buffer>,
+    pub kernel: Option<Buffer>,
 }
 
+// Safety: `UnsafeCommandPool` is not `Sync`, which makes its `Arc` neither `Send` nor `Sync`. The
+// pool is only kept alive here and never accessed through the `Arc`, and it is `Send` itself.
+unsafe impl Send for KeepAlive {}
+
 #[repr(C)]
 pub(crate) struct ConfigGuard {
     pub(crate) keep_alive: KeepAlive,
     pub(crate) config: vkfft_src::VkFFTConfiguration,
-    pub(crate) physical_device: vk_sys::PhysicalDevice,
-    // pub(crate) physical_device: ash::vk::PhysicalDevice,
-
-
-    pub(crate) device: vk_sys::Device,
-    pub(crate) queue: vk_sys::Queue,
-    pub(crate) command_pool: vk_sys::CommandPool,
-    pub(crate) fence: vk_sys::Fence,
+    pub(crate) physical_device: vk::PhysicalDevice,
+    pub(crate) device: vk::Device,
+    pub(crate) queue: vk::Queue,
+    pub(crate) command_pool: vk::CommandPool,
+    pub(crate) fence: vk::Fence,
     pub(crate) buffer_size: u64,
-    pub(crate) buffer: Option<vk_sys::Buffer>,
+    pub(crate) buffer: Option<vk::Buffer>,
     pub(crate) input_buffer_size: u64,
-    pub(crate) input_buffer: Option<vk_sys::Buffer>,
+    pub(crate) input_buffer: Option<vk::Buffer>,
     pub(crate) output_buffer_size: u64,
-    pub(crate) output_buffer: Option<vk_sys::Buffer>,
+    pub(crate) output_buffer: Option<vk::Buffer>,
     pub(crate) temp_buffer_size: u64,
-    pub(crate) temp_buffer: Option<vk_sys::Buffer>,
+    pub(crate) temp_buffer: Option<vk::Buffer>,
     pub(crate) kernel_size: u64,
-    pub(crate) kernel: Option<vk_sys::Buffer>,
+    pub(crate) kernel: Option<vk::Buffer>,
+    /// The timestamp period and valid bits if launches are profiled.
+    pub(crate) profile: Option<(f32, u32)>,
+    /// Binaries VkFFT loads instead of compiling the shaders.
+    #[cfg(feature = "wisdom")]
+    pub(crate) application: Vec<u8>,
+    pub(crate) prime_sizes: Vec<u64>,
+    pub(crate) padded_sizes: Vec<u64>,
 }
 
-impl<'a> Config<'a> {
-    pub fn builder() -> ConfigBuilder<'a> {
+// Safety: the pointers in `config` point into the guard itself, which is pinned.
+unsafe impl Send for ConfigGuard {}
+
+impl Config {
+    pub fn builder() -> ConfigBuilder {
         ConfigBuilder::new()
     }
 
+    pub fn physical_device(&self) -> vk::PhysicalDevice {
+        self.physical_device
+    }
+
+    pub fn device(&self) -> &ash::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> vk::Queue {
+        self.queue
+    }
+
+    pub fn fence(&self) -> vk::Fence {
+        self.fence
+    }
+
+    pub fn command_pool(&self) -> vk::CommandPool {
+        self.command_pool
+    }
+
     pub fn buffer_size(&self) -> usize {
         self.buffer.as_ref().map(|b| b.size()).unwrap_or(0)
     }
@@ -548,69 +1226,69 @@ impl<'a> Config<'a> {
         self.use_lut
     }
 
-    pub(crate) fn as_sys(&self) -> Result<Pin<Box<ConfigGuard>>, ConfigError> {
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    pub fn dct(&self) -> Option<Dct> {
+        self.dct
+    }
+
+    pub fn profile(&self) -> bool {
+        self.profile
+    }
+
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self.timestamp_period
+    }
+
+    pub fn timestamp_valid_bits(&self) -> Option<u32> {
+        self.timestamp_valid_bits
+    }
+
+    pub fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    pub(crate) fn into_sys(self) -> Result<Pin<Box<ConfigGuard>>, ConfigError> {
         use std::mem::{transmute, zeroed};
 
         unsafe {
             let keep_alive = KeepAlive {
-                device: self.device.clone(),
-                buffer: self.buffer.as_ref().map(|b| b.as_buffer().cloned()).flatten(),
-                input_buffer: self.input_buffer.as_ref().map(|b| b.as_buffer().cloned()).flatten(),
-                output_buffer: self.output_buffer.as_ref().map(|b| b.as_buffer().cloned()).flatten(),
-                kernel: self.kernel.as_ref().map(|b| b.as_buffer().cloned()).flatten(),
-                command_pool: self.command_pool.clone(),
-                queue: self.queue.clone(),
-                temp_buffer: self.temp_buffer.as_ref().map(|b| b.as_buffer().cloned()).flatten()
+                buffer: self.buffer.as_ref().and_then(|b| b.as_buffer().cloned()),
+                input_buffer: self.input_buffer.as_ref().and_then(|b| b.as_buffer().cloned()),
+                output_buffer: self.output_buffer.as_ref().and_then(|b| b.as_buffer().cloned()),
+                kernel: self.kernel.as_ref().and_then(|b| b.as_buffer().cloned()),
+                temp_buffer: self.temp_buffer.as_ref().and_then(|b| b.as_buffer().cloned()),
+                ..self.keep_alive
             };
 
-            let queue = {
-                let g = self.queue.internal_object_guard();
-                (*g).as_raw() as usize
-            } ;
-
             let mut res = Box::pin(ConfigGuard {
-                keep_alive,
                 config: zeroed(),
-                physical_device: self.physical_device.internal_object().as_raw() as usize,
-                device: self.device.internal_object().as_raw() as usize,
-                queue,
-                command_pool: self.command_pool.internal_object().as_raw() as u64,
-                fence: self.fence.internal_object().as_raw() as u64,
+                physical_device: self.physical_device,
+                device: self.device.handle(),
+                queue: self.queue,
+                command_pool: self.command_pool,
+                fence: self.fence,
                 buffer_size: self.buffer.as_ref().map(|b| b.size()).unwrap_or(0) as u64,
                 temp_buffer_size: self.temp_buffer.as_ref().map(|b| b.size()).unwrap_or(0) as u64,
                 input_buffer_size: self.input_buffer.as_ref().map(|b| b.size()).unwrap_or(0) as u64,
                 output_buffer_size: self.output_buffer.as_ref().map(|b| b.size()).unwrap_or(0) as u64,
                 kernel_size: self.kernel.as_ref().map(|b| b.size()).unwrap_or(0) as u64,
-                buffer: self
-                    .buffer
-                    .as_ref()
-                    .map(|b| b.as_buffer())
-                    .flatten()
-                    .map(|b| b.inner().buffer.internal_object().as_raw()),
-                temp_buffer: self
-                    .temp_buffer
-                    .as_ref()
-                    .map(|b| b.as_buffer())
-                    .flatten()
-                    .map(|b| b.inner().buffer.internal_object().as_raw()),
-                input_buffer: self
-                    .input_buffer
-                    .as_ref()
-                    .map(|b| b.as_buffer())
-                    .flatten()
-                    .map(|b| b.inner().buffer.internal_object().as_raw()),
-                output_buffer: self
-                    .output_buffer
-                    .as_ref()
-                    .map(|b| b.as_buffer())
-                    .flatten()
-                    .map(|b| b.inner().buffer.internal_object().as_raw()),
-                kernel: self
-                    .kernel
-                    .as_ref()
-                    .map(|b| b.as_buffer())
-                    .flatten()
-                    .map(|b| b.inner().buffer.internal_object().as_raw()),
+                buffer: keep_alive.buffer.as_ref().map(Buffer::handle),
+                temp_buffer: keep_alive.temp_buffer.as_ref().map(Buffer::handle),
+                input_buffer: keep_alive.input_buffer.as_ref().map(Buffer::handle),
+                output_buffer: keep_alive.output_buffer.as_ref().map(Buffer::handle),
+                kernel: keep_alive.kernel.as_ref().map(Buffer::handle),
+                profile: self
+                    .timestamp_period
+                    .filter(|_| self.profile)
+                    .map(|period| (period, self.timestamp_valid_bits.unwrap_or(64))),
+                prime_sizes: self.algorithm.prime_sizes.clone(),
+                padded_sizes: self.algorithm.padded_sizes.clone(),
+                #[cfg(feature = "wisdom")]
+                application: Vec::new(),
+                keep_alive,
             });
 
             res.config.FFTdim = self.fft_dim;
@@ -629,7 +1307,6 @@ impl<'a> Config<'a> {
             }
 
             if let Some(t) = &res.kernel {
-                println!("K: {:#0x}", t);
                 res.config.kernel = transmute(t);
             }
 
@@ -639,17 +1316,19 @@ impl<'a> Config<'a> {
             }
 
             if let Some(t) = &res.buffer {
-                println!("B: {:#0x}", *t);
                 res.config.buffer = transmute(t);
             }
 
+            if self.temp_buffer.is_some() {
+                res.config.userTempBuffer = 1;
+            }
+
             if res.temp_buffer_size != 0 {
                 res.config.tempBufferNum = 1;
                 res.config.tempBufferSize = transmute(addr_of_mut!(res.temp_buffer_size));
             }
 
             if let Some(t) = &res.temp_buffer {
-                println!("T: {:#0x}", *t);
                 res.config.tempBuffer = transmute(t);
             }
 
@@ -659,7 +1338,6 @@ impl<'a> Config<'a> {
             }
 
             if let Some(t) = &res.input_buffer {
-                println!("I: {:#0x}", *t);
                 res.config.inputBuffer = transmute(t);
             }
 
@@ -670,7 +1348,6 @@ impl<'a> Config<'a> {
             }
 
             if let Some(t) = &res.output_buffer {
-                println!("O: {:#0x}", *t);
                 res.config.outputBuffer = transmute(t);
             }
 
@@ -681,12 +1358,61 @@ impl<'a> Config<'a> {
             res.config.fft_zeropad_left = self.zeropad_left;
             res.config.fft_zeropad_right = self.zeropad_right;
 
+            res.config.omitDimension[0] = self.omit_dimension[0].into();
+            res.config.omitDimension[1] = self.omit_dimension[1].into();
+            res.config.omitDimension[2] = self.omit_dimension[2].into();
+
+            res.config.bufferStride = self.buffer_stride;
+            res.config.inputBufferStride = self.input_buffer_stride;
+            res.config.outputBufferStride = self.output_buffer_stride;
+
             res.config.kernelConvolution = self.kernel_convolution.into();
+            res.config.performConvolution = self.convolution.into();
             res.config.performR2C = self.r2c.into();
             res.config.coordinateFeatures = self.coordinate_features;
             res.config.disableReorderFourStep = self.disable_reorder_four_step.into();
 
             res.config.symmetricKernel = self.symmetric_kernel.into();
+            res.config.keepShaderCode = self.debug_info.into();
+
+            let algorithm = &self.algorithm;
+            res.config.fixMaxRadixBluestein = algorithm.fix_max_radix_bluestein.unwrap_or(0);
+            res.config.forceBluesteinSequenceSize = algorithm.force_bluestein_sequence_size.unwrap_or(0);
+            res.config.fixMinRaderPrimeMult = algorithm.fix_min_rader_prime_mult.unwrap_or(0);
+            res.config.fixMaxRaderPrimeMult = algorithm.fix_max_rader_prime_mult.unwrap_or(0);
+            res.config.fixMinRaderPrimeFFT = algorithm.fix_min_rader_prime_fft.unwrap_or(0);
+            res.config.fixMaxRaderPrimeFFT = algorithm.fix_max_rader_prime_fft.unwrap_or(0);
+
+            let tuning = &self.tuning;
+            res.config.registerBoost = tuning.register_boost.unwrap_or(0);
+            res.config.registerBoostNonPow2 = tuning.register_boost_non_pow2.into();
+            res.config.registerBoost4Step = tuning.register_boost_4_step.unwrap_or(0);
+            res.config.performBandwidthBoost = tuning.bandwidth_boost.unwrap_or(0) as u64;
+            res.config.coalescedMemory = tuning.coalesced_memory.unwrap_or(0);
+            res.config.aimThreads = tuning.aim_threads.unwrap_or(0);
+            res.config.numSharedBanks = tuning.num_shared_banks.unwrap_or(0);
+            res.config.swapTo3Stage4Step = tuning.swap_to_3_stage_4_step.unwrap_or(0);
+            res.config.useLUT = match tuning.use_lut {
+                _ if self.use_lut => 1,
+                Some(true) => 1,
+                Some(false) => -1,
+                None => 0,
+            };
+            res.config.useLUT_4step = match tuning.use_lut_4_step {
+                Some(true) => 1,
+                Some(false) => -1,
+                None => 0,
+            };
+
+            if !res.prime_sizes.is_empty() {
+                res.config.useCustomBluesteinPaddingPattern = res.prime_sizes.len() as u64;
+                res.config.primeSizes = res.prime_sizes.as_mut_ptr();
+                res.config.paddedSizes = res.padded_sizes.as_mut_ptr();
+            }
+
+            if let Some(dct) = self.dct {
+                res.config.performDCT = dct as u64;
+            }
 
             if let Some(input_formatted) = self.input_formatted {
                 res.config.isInputFormatted = input_formatted.into();
@@ -718,6 +1444,14 @@ impl<'a> Config<'a> {
                 _ => {}
             }
 
+            if self.inverse_return_to_input_buffer {
+                if res.config.isInputFormatted == 0 {
+                    return Err(ConfigError::InvalidConfig);
+                }
+
+                res.config.inverseReturnToInputBuffer = true.into();
+            }
+
             if let Some(batch_count) = &self.batch_count {
                 res.config.numberBatches = *batch_count;
             }
@@ -725,4 +1459,45 @@ impl<'a> Config<'a> {
             Ok(res)
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(precision: Option<Precision>) -> Buffer {
+        Buffer {
+            precision,
+            // Safety: never handed to VkFFT.
+            ..unsafe { Buffer::from_raw(vk::Buffer::null(), 64) }
+        }
+    }
+
+    #[test]
+    fn infer_precision() {
+        let single = || buffer(Some(Precision::Single));
+        let half = || buffer(Some(Precision::Half));
+        let double = || buffer(Some(Precision::Double));
+
+        assert_eq!(ConfigBuilder::new().infer_precision().unwrap(), Precision::Single);
+        assert_eq!(ConfigBuilder::new().buffer(64).infer_precision().unwrap(), Precision::Single);
+        assert_eq!(ConfigBuilder::new().buffer(buffer(None)).infer_precision().unwrap(), Precision::Single);
+
+        let builder = ConfigBuilder::new().buffer(double()).temp_buffer(64);
+        assert_eq!(builder.infer_precision().unwrap(), Precision::Double);
+
+        let builder = ConfigBuilder::new().input_buffer(double()).output_buffer(buffer(None));
+        assert_eq!(builder.infer_precision().unwrap(), Precision::Double);
+
+        let builder = ConfigBuilder::new().buffer(single()).input_buffer(half()).output_buffer(half());
+        assert_eq!(builder.infer_precision().unwrap(), Precision::HalfMemory);
+
+        let builder = ConfigBuilder::new().buffer(double()).kernel(single());
+        assert!(matches!(builder.infer_precision(), Err(BuildError::PrecisionMismatch)));
+
+        let builder = ConfigBuilder::new().input_buffer(half()).output_buffer(single());
+        assert!(matches!(builder.infer_precision(), Err(BuildError::PrecisionMismatch)));
+
+        let builder = ConfigBuilder::new().buffer(double()).input_buffer(half());
+        assert!(matches!(builder.infer_precision(), Err(BuildError::PrecisionMismatch)));
+    }
+}
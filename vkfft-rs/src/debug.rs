@@ -0,0 +1,161 @@
+use std::ffi::{c_void, CStr};
+
+use vkfft_src::{VkBuffer, VkFFTApplication, VkFFTAxis, VkFFTConfiguration, VkFFTPlan};
+
+use crate::app::BufferKind;
+
+extern "C" {
+    fn free(ptr: *mut c_void);
+}
+
+/// The shaders VkFFT generated for a plan and the buffers they use, collected for plans built
+/// with [`debug_info`](crate::config::ConfigBuilder::debug_info).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    /// One entry per shader, in the order of the plans.
+    pub shaders: Vec<ShaderInfo>,
+}
+
+/// Which part of an axis a shader computes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    /// A pass of the FFT along the axis.
+    Axis,
+    /// A pass of the inverse FFT Bluestein's algorithm runs after the multiplication with the
+    /// chirp, for axes transformed in several passes.
+    BluesteinInverse,
+    /// The decomposition of the complex result into the real one, for R2C transforms of axes
+    /// transformed in several passes.
+    R2cDecomposition,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderInfo {
+    /// Whether the shader belongs to the inverse plan.
+    pub inverse: bool,
+    pub axis: usize,
+    /// The pass of the axis, in VkFFT's order, the last of which is dispatched first.
+    pub pass: usize,
+    pub kind: ShaderKind,
+    /// The generated GLSL.
+    pub code: String,
+    /// The buffer the shader read from in the last launch, or as set up when the plan was created
+    /// if it wasn't launched yet. `None` if the buffer wasn't known yet.
+    pub read: Option<BufferKind>,
+    /// The buffer the shader wrote to, like `read`.
+    pub write: Option<BufferKind>,
+}
+
+/// The shaders of both plans of `app`.
+///
+/// # Safety
+///
+/// `app` must be initialized by `initializeVkFFT`.
+unsafe fn shaders(app: &VkFFTApplication) -> Vec<(bool, usize, usize, ShaderKind, *mut VkFFTAxis)> {
+    let dim = (app.configuration.FFTdim as usize).min(3);
+    let mut res = Vec::new();
+
+    for (inverse, plan) in [(false, app.localFFTPlan), (true, app.localFFTPlan_inverse)] {
+        let plan: &mut VkFFTPlan = match plan.as_mut() {
+            Some(plan) => plan,
+            None => continue,
+        };
+
+        for axis in 0..dim {
+            let uploads = (plan.numAxisUploads[axis] as usize).min(plan.axes[axis].len());
+
+            for pass in 0..uploads {
+                res.push((inverse, axis, pass, ShaderKind::Axis, &mut plan.axes[axis][pass] as *mut _));
+            }
+
+            if app.useBluesteinFFT[axis] != 0 && uploads > 1 {
+                for pass in 1..uploads {
+                    let shader = &mut plan.inverseBluesteinAxes[axis][pass] as *mut _;
+                    res.push((inverse, axis, pass, ShaderKind::BluesteinInverse, shader));
+                }
+            }
+        }
+
+        if plan.multiUploadR2C != 0 {
+            res.push((inverse, 0, 0, ShaderKind::R2cDecomposition, &mut plan.R2Cdecomposition as *mut _));
+        }
+    }
+
+    res
+}
+
+/// Names the buffer `buffer` points to, like VkFFT's `printMemoryLayout`.
+fn buffer_kind(config: &VkFFTConfiguration, buffer: *mut VkBuffer) -> Option<BufferKind> {
+    if buffer.is_null() {
+        None
+    } else if buffer == config.buffer {
+        Some(BufferKind::Buffer)
+    } else if buffer == config.tempBuffer {
+        Some(BufferKind::TempBuffer)
+    } else if buffer == config.inputBuffer {
+        Some(BufferKind::InputBuffer)
+    } else if buffer == config.outputBuffer {
+        Some(BufferKind::OutputBuffer)
+    } else {
+        None
+    }
+}
+
+impl DebugInfo {
+    /// Takes the shader code VkFFT kept because of `keepShaderCode` and turns the option off, so
+    /// VkFFT doesn't print the shaders on every launch.
+    ///
+    /// # Safety
+    ///
+    /// `app` must be initialized by `initializeVkFFT` with `keepShaderCode` set.
+    pub(crate) unsafe fn take(app: &mut VkFFTApplication) -> Self {
+        let mut res = Self::default();
+
+        for (inverse, axis, pass, kind, shader) in shaders(app) {
+            let constants = &mut (*shader).specializationConstants;
+
+            if constants.code0.is_null() {
+                continue;
+            }
+
+            let code = CStr::from_ptr(constants.code0).to_string_lossy().into_owned();
+
+            // VkFFT doesn't free code it was asked to keep.
+            free(constants.code0.cast());
+            constants.code0 = std::ptr::null_mut();
+
+            res.shaders.push(ShaderInfo {
+                inverse,
+                axis,
+                pass,
+                kind,
+                code,
+                read: buffer_kind(&app.configuration, (*shader).inputBuffer),
+                write: buffer_kind(&app.configuration, (*shader).outputBuffer),
+            });
+        }
+
+        app.configuration.keepShaderCode = 0;
+        res
+    }
+
+    /// Records the buffers the shaders used in the launch VkFFT just recorded. The buffers of the
+    /// launch must still be alive, as VkFFT compares pointers to them.
+    ///
+    /// # Safety
+    ///
+    /// `app` must be the application `take` was called on.
+    pub(crate) unsafe fn update_layout(&mut self, app: &VkFFTApplication) {
+        for (inverse, axis, pass, kind, shader) in shaders(app) {
+            let info = self
+                .shaders
+                .iter_mut()
+                .find(|s| (s.inverse, s.axis, s.pass, s.kind) == (inverse, axis, pass, kind));
+
+            if let Some(info) = info {
+                info.read = buffer_kind(&app.configuration, (*shader).inputBuffer);
+                info.write = buffer_kind(&app.configuration, (*shader).outputBuffer);
+            }
+        }
+    }
+}
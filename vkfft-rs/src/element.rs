@@ -0,0 +1,116 @@
+//! Element types of the buffers VkFFT reads and writes.
+//!
+//! Complex elements are stored as real part followed by imaginary part, which is the layout of
+//! `[T; 2]` and of `num_complex::Complex<T>` (with the `num-complex` feature). `half::f16` is
+//! available with the `half` feature.
+
+use std::any::TypeId;
+
+use bytemuck::Pod;
+
+use crate::config::Precision;
+
+/// A buffer element, along with the precision VkFFT needs to process it.
+pub trait Element: Pod + Send + Sync {
+    const PRECISION: Precision;
+}
+
+/// A real scalar VkFFT computes with: `f32`, `f64` and, with the `half` feature, `half::f16`.
+///
+/// This trait is sealed, so complex elements can't nest, e.g. `[[f32; 2]; 2]` is no element.
+pub trait RealElement: Element + private::Sealed {}
+
+/// A complex element made of two [`RealElement`]s.
+pub trait ComplexElement: Element {
+    type Real: RealElement;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+impl Element for f32 {
+    const PRECISION: Precision = Precision::Single;
+}
+
+impl private::Sealed for f32 {}
+impl RealElement for f32 {}
+
+impl Element for f64 {
+    const PRECISION: Precision = Precision::Double;
+}
+
+impl private::Sealed for f64 {}
+impl RealElement for f64 {}
+
+#[cfg(feature = "half")]
+impl Element for half::f16 {
+    const PRECISION: Precision = Precision::Half;
+}
+
+#[cfg(feature = "half")]
+impl private::Sealed for half::f16 {}
+#[cfg(feature = "half")]
+impl RealElement for half::f16 {}
+
+impl<T: RealElement> Element for [T; 2] {
+    const PRECISION: Precision = T::PRECISION;
+}
+
+impl<T: RealElement> ComplexElement for [T; 2] {
+    type Real = T;
+}
+
+#[cfg(feature = "num-complex")]
+impl<T: RealElement> Element for num_complex::Complex<T> {
+    const PRECISION: Precision = T::PRECISION;
+}
+
+#[cfg(feature = "num-complex")]
+impl<T: RealElement> ComplexElement for num_complex::Complex<T> {
+    type Real = T;
+}
+
+/// The precision of buffers with contents `C`, for slices of the element types above. `None` for
+/// other contents, e.g. raw bytes.
+pub(crate) fn content_precision<C: ?Sized + 'static>() -> Option<Precision> {
+    fn slice<E: Element>() -> (TypeId, Precision) {
+        (TypeId::of::<[E]>(), E::PRECISION)
+    }
+
+    #[allow(unused_mut)]
+    let mut known = vec![slice::<f32>(), slice::<f64>(), slice::<[f32; 2]>(), slice::<[f64; 2]>()];
+
+    #[cfg(feature = "half")]
+    known.extend([slice::<half::f16>(), slice::<[half::f16; 2]>()]);
+
+    #[cfg(feature = "num-complex")]
+    known.extend([slice::<num_complex::Complex<f32>>(), slice::<num_complex::Complex<f64>>()]);
+
+    #[cfg(all(feature = "half", feature = "num-complex"))]
+    known.push(slice::<num_complex::Complex<half::f16>>());
+
+    known
+        .into_iter()
+        .find(|&(id, _)| id == TypeId::of::<C>())
+        .map(|(_, precision)| precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_precision_of_slices() {
+        assert_eq!(content_precision::<[f32]>(), Some(Precision::Single));
+        assert_eq!(content_precision::<[[f64; 2]]>(), Some(Precision::Double));
+        assert_eq!(content_precision::<[u8]>(), None);
+        assert_eq!(content_precision::<f32>(), None);
+
+        #[cfg(feature = "num-complex")]
+        assert_eq!(content_precision::<[num_complex::Complex<f64>]>(), Some(Precision::Double));
+
+        #[cfg(feature = "half")]
+        assert_eq!(content_precision::<[[half::f16; 2]]>(), Some(Precision::Half));
+    }
+}
@@ -3,7 +3,7 @@ use std::io::ErrorKind;
 
 use derive_more::{Display, Error};
 
-use crate::{app::LaunchError, config::ConfigError};
+use crate::{app::LaunchError, config::{self, ConfigError}};
 
 #[derive(Display, Debug, Error)]
 pub enum Error {
@@ -81,8 +81,19 @@ pub enum Error {
     FailedToCreateCommandQueue,
     FailedToReleaseCommandQueue,
     FailedToEnumerateDevices,
+    /// Creating the timestamp queries of a profiled `App` failed
+    FailedToCreateQueryPool,
+    /// Reading the timestamps of a profiled launch failed
+    FailedToGetQueryResults,
     Config(ConfigError),
+    ConfigBuild(config::BuildError),
     Launch(LaunchError),
+    #[cfg(feature = "vulkano")]
+    Host(crate::host::HostError),
+    #[cfg(feature = "ndarray")]
+    Array(crate::array::ArrayError),
+    #[cfg(feature = "autotune")]
+    Tuning(crate::autotune::TuningError),
 }
 
 impl TryFrom<vkfft_src::VkFFTResult> for Error {
@@ -202,12 +213,39 @@ impl From<ConfigError> for Error {
     }
 }
 
+impl From<config::BuildError> for Error {
+    fn from(e: config::BuildError) -> Self {
+        Self::ConfigBuild(e)
+    }
+}
+
 impl From<LaunchError> for Error {
     fn from(e: LaunchError) -> Self {
         Self::Launch(e)
     }
 }
 
+#[cfg(feature = "vulkano")]
+impl From<crate::host::HostError> for Error {
+    fn from(e: crate::host::HostError) -> Self {
+        Self::Host(e)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl From<crate::array::ArrayError> for Error {
+    fn from(e: crate::array::ArrayError) -> Self {
+        Self::Array(e)
+    }
+}
+
+#[cfg(feature = "autotune")]
+impl From<crate::autotune::TuningError> for Error {
+    fn from(e: crate::autotune::TuningError) -> Self {
+        Self::Tuning(e)
+    }
+}
+
 pub(crate) fn check_error(result: vkfft_src::VkFFTResult) -> Result<()> {
     match result.try_into() {
         Ok(err) => Err(err),
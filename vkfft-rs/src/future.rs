@@ -0,0 +1,195 @@
+use std::future::Future;
+use std::ops::DerefMut;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once};
+use std::task::{Context, Poll, Waker};
+
+use ash::vk;
+
+use crate::{app::{App, RecordedFft}, error};
+
+#[derive(Default)]
+struct State {
+    signaled: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// Resolves once a submission made by [`App::execute_async`] or [`App::into_execute_async`] has
+/// finished on the GPU, to the plan the submission was made with.
+///
+/// One helper thread waits on the fences of all pending futures, so the future works with any
+/// executor. With [`App::into_execute_async`] the future owns the plan and can be spawned. Dropping
+/// the future before it resolves blocks until the GPU work has finished, since the `App` and its
+/// command buffer must not be reused while they are still in flight.
+pub struct FftFuture<A: DerefMut<Target = App>> {
+    app: Option<A>,
+    recorded: Option<RecordedFft>,
+    state: Arc<Mutex<State>>,
+}
+
+impl<A: DerefMut<Target = App>> FftFuture<A> {
+    pub(crate) fn new(app: A, recorded: RecordedFft) -> Self {
+        let state = Arc::new(Mutex::new(State::default()));
+        watch(app.device.clone(), app.config.fence, state.clone());
+
+        Self {
+            app: Some(app),
+            recorded: Some(recorded),
+            state,
+        }
+    }
+}
+
+impl<A: DerefMut<Target = App> + Unpin> Future for FftFuture<A> {
+    type Output = error::Result<A>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.app.is_none() {
+            panic!("FftFuture polled after completion");
+        }
+
+        let signaled = {
+            let mut state = lock(&this.state);
+
+            match state.signaled {
+                Some(signaled) => signaled,
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+
+        let mut app = this.app.take().expect("checked above");
+
+        // A failed wait leaves the command buffer pending, in which case it can't be freed and
+        // `recorded` leaks the buffers.
+        let recorded = this.recorded.take().expect("recorded until completion");
+
+        if !signaled {
+            return Poll::Ready(Err(error::Error::FailedToWaitForFences));
+        }
+
+        Poll::Ready(app.complete(recorded).map(|()| app))
+    }
+}
+
+impl<A: DerefMut<Target = App>> Drop for FftFuture<A> {
+    fn drop(&mut self) {
+        if let (Some(app), Some(recorded)) = (&mut self.app, self.recorded.take()) {
+            let fence = app.config.fence;
+            unwatch(fence);
+
+            if unsafe { app.device.wait_for_fences(&[fence], true, u64::MAX) }.is_ok() {
+                let _ = app.complete(recorded);
+            }
+        }
+    }
+}
+
+/// A fence some future is waiting for.
+struct Pending {
+    device: ash::Device,
+    fence: vk::Fence,
+    state: Arc<Mutex<State>>,
+}
+
+impl Pending {
+    fn finish(&self, signaled: bool) {
+        let mut state = lock(&self.state);
+        state.signaled = Some(signaled);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+struct Fences {
+    pending: Vec<Pending>,
+    /// The fences the waiter thread is blocked on right now, without holding the lock.
+    waiting: Vec<vk::Fence>,
+}
+
+static FENCES: Mutex<Fences> = Mutex::new(Fences {
+    pending: Vec::new(),
+    waiting: Vec::new(),
+});
+
+/// Signals new pending fences to the waiter and the end of a wait to [`unwatch`].
+static CHANGED: Condvar = Condvar::new();
+
+static WAITER: Once = Once::new();
+
+/// How long the waiter blocks on the fences of one device before it looks at the others again.
+const WAIT_SLICE_NS: u64 = 1_000_000;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Resolves `state` once `fence` has signaled.
+fn watch(device: ash::Device, fence: vk::Fence, state: Arc<Mutex<State>>) {
+    WAITER.call_once(|| {
+        std::thread::Builder::new()
+            .name("vkfft-fences".to_string())
+            .spawn(wait_for_fences)
+            .expect("fence waiter thread");
+    });
+
+    lock(&FENCES).pending.push(Pending { device, fence, state });
+    CHANGED.notify_all();
+}
+
+/// Stops waiting for `fence`. Once this returns, the waiter doesn't use the fence anymore, so it
+/// may be waited on, reset or destroyed.
+fn unwatch(fence: vk::Fence) {
+    let mut fences = lock(&FENCES);
+    fences.pending.retain(|p| p.fence != fence);
+
+    while fences.waiting.contains(&fence) {
+        fences = CHANGED.wait(fences).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// The loop of the waiter thread.
+fn wait_for_fences() {
+    let mut fences = lock(&FENCES);
+
+    loop {
+        fences.pending.retain(|p| match unsafe { p.device.get_fence_status(p.fence) } {
+            Ok(false) => true,
+            res => {
+                p.finish(res.is_ok());
+                false
+            }
+        });
+
+        let device = match fences.pending.first() {
+            Some(first) => first.device.clone(),
+            None => {
+                fences = CHANGED.wait(fences).unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+        };
+
+        let waiting: Vec<vk::Fence> = fences
+            .pending
+            .iter()
+            .filter(|p| p.device.handle() == device.handle())
+            .map(|p| p.fence)
+            .collect();
+
+        fences.waiting = waiting.clone();
+        drop(fences);
+
+        // Any of them signaling is enough, the statuses are checked above.
+        let _ = unsafe { device.wait_for_fences(&waiting, false, WAIT_SLICE_NS) };
+
+        fences = lock(&FENCES);
+        fences.waiting.clear();
+        CHANGED.notify_all();
+    }
+}
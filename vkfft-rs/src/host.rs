@@ -0,0 +1,486 @@
+//! Transforms on host slices, for prototyping and tests.
+//!
+//! Every call stages the data in device-local buffers, runs the transform and downloads the result.
+//! Plans are built on first use and cached in the [`Context`] by shape, precision and transform
+//! type. Shapes are given in VkFFT order, i.e. the fastest varying axis first.
+//!
+//! The inverse transforms are normalized, so `ifft(fft(x)) == x` up to rounding.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytemuck::Zeroable;
+use derive_more::{Display, Error};
+use vulkano::{
+    buffer::{BufferUsage, CpuAccessibleBuffer, DeviceLocalBuffer},
+    command_buffer::{
+        pool::{UnsafeCommandPool, UnsafeCommandPoolCreateInfo},
+        AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, PrimaryAutoCommandBuffer,
+        PrimaryCommandBuffer,
+    },
+    device::{Device, DeviceOwned, Queue},
+    sync::GpuFuture,
+};
+
+use crate::{
+    app::{App, LaunchParams},
+    command_buffer::FftCommandBufferBuilderExt,
+    config::{Config, Precision},
+    element::ComplexElement,
+    error::{self, Error},
+};
+
+#[derive(Display, Debug, Error)]
+pub enum HostError {
+    /// The shape has a length of zero
+    EmptyShape,
+    #[display(fmt = "Shapes with {} dimensions are not supported, only 1 to 3", _0)]
+    UnsupportedDimensions(#[error(not(source))] usize),
+    #[display(fmt = "{} has {} elements, but the shape needs {}", name, len, expected)]
+    LengthMismatch {
+        name: &'static str,
+        len: usize,
+        expected: usize,
+    },
+}
+
+/// Everything a cached plan depends on. Strides are in elements, like for
+/// [`ConfigBuilder::buffer_stride`](crate::config::ConfigBuilder::buffer_stride).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct PlanKey {
+    pub(crate) fft_dim: usize,
+    pub(crate) size: [u64; 3],
+    pub(crate) omit: [bool; 3],
+    pub(crate) batches: u64,
+    pub(crate) precision: Precision,
+    pub(crate) r2c: bool,
+    /// Strides of `buffer`, which holds the complex side.
+    pub(crate) stride: [u64; 3],
+    /// Strides of `input_buffer`, which holds the real side of R2C plans.
+    pub(crate) input_stride: [u64; 3],
+}
+
+impl PlanKey {
+    fn new<const N: usize>(shape: &[u64; N], precision: Precision, r2c: bool) -> Result<Self, HostError> {
+        if N == 0 || N > 3 {
+            return Err(HostError::UnsupportedDimensions(N));
+        }
+
+        let mut size = [1; 3];
+        size[..N].copy_from_slice(shape);
+
+        let res = Self {
+            fft_dim: N,
+            size,
+            omit: [false; 3],
+            batches: 1,
+            precision,
+            r2c,
+            stride: Self::packed(Self::complex_x(size[0], r2c), &size),
+            input_stride: Self::packed(size[0], &size),
+        };
+
+        res.check()?;
+        Ok(res)
+    }
+
+    /// Rejects empty shapes, which neither VkFFT nor the buffer lengths can handle.
+    pub(crate) fn check(&self) -> Result<(), HostError> {
+        if self.size.contains(&0) || self.batches == 0 {
+            return Err(HostError::EmptyShape);
+        }
+
+        Ok(())
+    }
+
+    /// Packed strides of rows with `x` elements.
+    pub(crate) fn packed(x: u64, size: &[u64; 3]) -> [u64; 3] {
+        [x, x * size[1], x * size[1] * size[2]]
+    }
+
+    /// Number of complex elements along x, which R2C halves.
+    pub(crate) fn complex_x(x: u64, r2c: bool) -> u64 {
+        if r2c {
+            x / 2 + 1
+        } else {
+            x
+        }
+    }
+
+    fn span(&self, x: u64, stride: &[u64; 3]) -> usize {
+        let last = x - 1
+            + (self.size[1] - 1) * stride[0]
+            + (self.size[2] - 1) * stride[1]
+            + (self.batches - 1) * stride[2];
+
+        last as usize + 1
+    }
+
+    /// Length of `buffer` in complex elements.
+    pub(crate) fn buffer_len(&self) -> usize {
+        self.span(Self::complex_x(self.size[0], self.r2c), &self.stride)
+    }
+
+    /// Length of `input_buffer` in real elements.
+    pub(crate) fn input_len(&self) -> usize {
+        self.span(self.size[0], &self.input_stride)
+    }
+
+    fn scalar_size(&self) -> usize {
+        match self.precision {
+            Precision::Double => 8,
+            Precision::Half => 2,
+            _ => 4,
+        }
+    }
+}
+
+/// The device and queue the host transforms run on, along with the plans built so far.
+pub struct Context {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    plans: Mutex<HashMap<PlanKey, Pin<Box<App>>>>,
+}
+
+impl Context {
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self {
+            device,
+            queue,
+            plans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Arc<Queue> {
+        &self.queue
+    }
+
+    /// Drops all cached plans.
+    pub fn clear(&self) {
+        self.plans.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    fn plan(&self, key: &PlanKey) -> error::Result<Pin<Box<App>>> {
+        let command_pool = UnsafeCommandPool::new(
+            self.device.clone(),
+            UnsafeCommandPoolCreateInfo {
+                queue_family_index: self.queue.queue_family_index(),
+                reset_command_buffer: true,
+                ..Default::default()
+            },
+        )
+        .map_err(|_| Error::FailedToCreateCommandPool)?;
+
+        // Each plan gets its own pool, which is only used through the `App` owning it.
+        #[allow(clippy::arc_with_non_send_sync)]
+        let command_pool = Arc::new(command_pool);
+
+        let mut builder = Config::builder()
+            .physical_device(self.device.physical_device().clone())
+            .device(self.device.clone())
+            .queue(self.queue.clone())
+            .command_pool(command_pool)
+            .precision(key.precision)
+            .normalize()
+            .omit_dimension(&key.omit)
+            .buffer(key.buffer_len() * 2 * key.scalar_size())
+            .buffer_stride(&key.stride);
+
+        builder = match key.fft_dim {
+            1 => builder.dim(&[key.size[0]]),
+            2 => builder.dim(&[key.size[0], key.size[1]]),
+            _ => builder.dim(&key.size),
+        };
+
+        if key.batches > 1 {
+            builder = builder.batch_count(key.batches);
+        }
+
+        // The real side lives unpadded in the input buffer for both directions.
+        if key.r2c {
+            builder = builder
+                .r2c()
+                .input_buffer(key.input_len() * key.scalar_size())
+                .input_buffer_stride(&key.input_stride)
+                .input_formatted(true)
+                .inverse_return_to_input_buffer();
+        }
+
+        App::new(builder.build()?)
+    }
+
+    /// Runs the plan for `key` on `buffer` and, for R2C plans, `input_buffer`.
+    ///
+    /// The plan is taken out of the cache while it runs, so the cache isn't locked while waiting for
+    /// the GPU. Concurrent calls with the same key build a plan of their own, and one of the plans is
+    /// kept afterwards.
+    pub(crate) fn run(&self, key: PlanKey, inverse: bool, buffer: Data, input_buffer: Option<Data>) -> error::Result<()> {
+        key.check()?;
+
+        let cached = self.plans.lock().unwrap_or_else(|e| e.into_inner()).remove(&key);
+        let mut app = match cached {
+            Some(app) => app,
+            None => self.plan(&key)?,
+        };
+
+        let res = self.run_plan(&mut app, inverse, buffer, input_buffer);
+
+        self.plans.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_insert(app);
+        res
+    }
+
+    fn run_plan(&self, app: &mut App, inverse: bool, buffer: Data, input_buffer: Option<Data>) -> error::Result<()> {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.device.clone(),
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .map_err(|_| Error::FailedToBeginCommandBuffer)?;
+
+        let buffer = Staging::upload(&self.queue, &mut builder, buffer)?;
+        let input_buffer = input_buffer
+            .map(|data| Staging::upload(&self.queue, &mut builder, data))
+            .transpose()?;
+
+        let mut params = LaunchParams::builder().buffer(buffer.device.clone());
+
+        if let Some(input_buffer) = &input_buffer {
+            params = params.input_buffer(input_buffer.device.clone());
+        }
+
+        builder.fft(app, params, inverse)?;
+
+        buffer.download(&mut builder)?;
+
+        if let Some(input_buffer) = &input_buffer {
+            input_buffer.download(&mut builder)?;
+        }
+
+        builder
+            .build()
+            .map_err(|_| Error::FailedToEndCommandBuffer)?
+            .execute(self.queue.clone())
+            .map_err(|_| Error::FailedToSubmitQueue)?
+            .then_signal_fence_and_flush()
+            .map_err(|_| Error::FailedToSubmitQueue)?
+            .wait(None)
+            .map_err(|_| Error::FailedToWaitForFences)?;
+
+        buffer.finish()?;
+
+        if let Some(input_buffer) = input_buffer {
+            input_buffer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Host side of a staged buffer.
+pub(crate) enum Data<'a> {
+    In(&'a [u8]),
+    Out(&'a mut [u8]),
+    InOut(&'a mut [u8]),
+}
+
+struct Staging<'a> {
+    data: Data<'a>,
+    host: Arc<CpuAccessibleBuffer<[u8]>>,
+    device: Arc<DeviceLocalBuffer<[u8]>>,
+}
+
+impl<'a> Staging<'a> {
+    fn upload(
+        queue: &Arc<Queue>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        data: Data<'a>,
+    ) -> error::Result<Self> {
+        let usage = BufferUsage {
+            transfer_src: true,
+            transfer_dst: true,
+            ..BufferUsage::empty()
+        };
+
+        let (bytes, upload): (&[u8], _) = match &data {
+            Data::In(bytes) => (bytes, true),
+            Data::InOut(bytes) => (bytes, true),
+            Data::Out(bytes) => (bytes, false),
+        };
+
+        let host = CpuAccessibleBuffer::from_iter(queue.device().clone(), usage, !upload, bytes.iter().copied())
+            .map_err(|_| Error::FailedToAllocateMemory)?;
+
+        let device = DeviceLocalBuffer::array(
+            queue.device().clone(),
+            bytes.len() as u64,
+            BufferUsage {
+                storage_buffer: true,
+                ..usage
+            },
+            [queue.queue_family_index()],
+        )
+        .map_err(|_| Error::FailedToAllocateMemory)?;
+
+        if upload {
+            builder
+                .copy_buffer(CopyBufferInfo::buffers(host.clone(), device.clone()))
+                .map_err(|_| Error::FailedToCopy)?;
+        }
+
+        Ok(Self { data, host, device })
+    }
+
+    fn download(&self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> error::Result<()> {
+        if let Data::In(_) = self.data {
+            return Ok(());
+        }
+
+        builder
+            .copy_buffer(CopyBufferInfo::buffers(self.device.clone(), self.host.clone()))
+            .map_err(|_| Error::FailedToCopy)?;
+
+        Ok(())
+    }
+
+    fn finish(self) -> error::Result<()> {
+        match self.data {
+            Data::In(_) => Ok(()),
+            Data::Out(bytes) | Data::InOut(bytes) => {
+                let host = self.host.read().map_err(|_| Error::FailedToMapMemory)?;
+                bytes.copy_from_slice(&host);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn check_len(name: &'static str, len: usize, expected: usize) -> Result<(), HostError> {
+    if len != expected {
+        return Err(HostError::LengthMismatch { name, len, expected });
+    }
+
+    Ok(())
+}
+
+/// Forward complex transform of `data` in place.
+pub fn fft<C: ComplexElement, const N: usize>(ctx: &Context, data: &mut [C], shape: &[u64; N]) -> error::Result<()> {
+    c2c(ctx, data, shape, false)
+}
+
+/// Normalized inverse complex transform of `data` in place.
+pub fn ifft<C: ComplexElement, const N: usize>(ctx: &Context, data: &mut [C], shape: &[u64; N]) -> error::Result<()> {
+    c2c(ctx, data, shape, true)
+}
+
+fn c2c<C: ComplexElement, const N: usize>(ctx: &Context, data: &mut [C], shape: &[u64; N], inverse: bool) -> error::Result<()> {
+    let key = PlanKey::new(shape, C::PRECISION, false)?;
+    check_len("data", data.len(), key.buffer_len())?;
+
+    ctx.run(key, inverse, Data::InOut(bytemuck::cast_slice_mut(data)), None)
+}
+
+/// Forward transform of real `input` with the real `shape`. Returns the non-redundant half of the
+/// spectrum, with `shape[0] / 2 + 1` elements along the first axis, as the complex type `C`.
+pub fn rfft<C: ComplexElement, const N: usize>(ctx: &Context, input: &[C::Real], shape: &[u64; N]) -> error::Result<Vec<C>> {
+    let key = PlanKey::new(shape, C::PRECISION, true)?;
+    check_len("input", input.len(), key.input_len())?;
+
+    let mut output = vec![C::zeroed(); key.buffer_len()];
+
+    ctx.run(
+        key,
+        false,
+        Data::Out(bytemuck::cast_slice_mut(&mut output)),
+        Some(Data::In(bytemuck::cast_slice(input))),
+    )?;
+
+    Ok(output)
+}
+
+/// Normalized inverse of [`rfft`], producing real data with the real `shape`.
+pub fn irfft<C: ComplexElement, const N: usize>(ctx: &Context, input: &[C], shape: &[u64; N]) -> error::Result<Vec<C::Real>> {
+    let key = PlanKey::new(shape, C::PRECISION, true)?;
+    check_len("input", input.len(), key.buffer_len())?;
+
+    let mut output = vec![C::Real::zeroed(); key.input_len()];
+
+    ctx.run(
+        key,
+        true,
+        Data::In(bytemuck::cast_slice(input)),
+        Some(Data::Out(bytemuck::cast_slice_mut(&mut output))),
+    )?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_lengths() {
+        let key = PlanKey::new(&[4, 3], Precision::Single, false).unwrap();
+        assert_eq!(key.stride, [4, 12, 12]);
+        assert_eq!(key.buffer_len(), 12);
+
+        // The complex side of R2C holds `x / 2 + 1` elements per row, the real side `x`.
+        let key = PlanKey::new(&[6, 2], Precision::Double, true).unwrap();
+        assert_eq!(key.stride, [4, 8, 8]);
+        assert_eq!(key.input_stride, [6, 12, 12]);
+        assert_eq!(key.buffer_len(), 8);
+        assert_eq!(key.input_len(), 12);
+    }
+
+    #[test]
+    fn strided_span() {
+        let key = PlanKey {
+            size: [3, 2, 1],
+            batches: 2,
+            stride: [4, 8, 10],
+            ..PlanKey::new(&[3, 2], Precision::Single, false).unwrap()
+        };
+
+        // Ends at x = 2 of the second row of the second batch.
+        assert_eq!(key.buffer_len(), 2 + 4 + 10 + 1);
+    }
+
+    #[test]
+    fn empty_shape() {
+        assert!(matches!(PlanKey::new(&[0, 4], Precision::Single, false), Err(HostError::EmptyShape)));
+        assert!(matches!(PlanKey::new(&[4, 0], Precision::Single, true), Err(HostError::EmptyShape)));
+
+        let key = PlanKey {
+            batches: 0,
+            ..PlanKey::new(&[4], Precision::Single, false).unwrap()
+        };
+        assert!(matches!(key.check(), Err(HostError::EmptyShape)));
+    }
+
+    #[test]
+    fn unsupported_dimensions() {
+        assert!(matches!(
+            PlanKey::new(&[], Precision::Single, false),
+            Err(HostError::UnsupportedDimensions(0))
+        ));
+        assert!(matches!(
+            PlanKey::new(&[2, 2, 2, 2], Precision::Single, false),
+            Err(HostError::UnsupportedDimensions(4))
+        ));
+        assert!(PlanKey::new(&[2, 2, 2], Precision::Single, false).is_ok());
+    }
+
+    #[test]
+    fn length_mismatch() {
+        assert!(check_len("data", 12, 12).is_ok());
+        assert!(matches!(
+            check_len("data", 11, 12),
+            Err(HostError::LengthMismatch { name: "data", len: 11, expected: 12 })
+        ));
+    }
+}
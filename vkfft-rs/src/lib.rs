@@ -1,9 +1,36 @@
 extern crate vkfft_src as vk;
 pub mod config;
 pub mod app;
+pub mod debug;
 pub mod error;
+pub mod element;
+pub mod future;
+pub mod nd;
+pub mod plan;
+pub mod profile;
+#[cfg(feature = "vulkano")]
+pub mod command_buffer;
+#[cfg(feature = "vulkano")]
+pub mod host;
+#[cfg(feature = "ndarray")]
+pub mod array;
+#[cfg(feature = "reference")]
+pub mod reference;
+#[cfg(feature = "autotune")]
+pub mod autotune;
+#[cfg(feature = "wisdom")]
+pub mod wisdom;
 mod version;
 pub use version::*;
+pub use element::{ComplexElement, Element, RealElement};
+#[cfg(feature = "half")]
+pub use half;
+#[cfg(feature = "num-complex")]
+pub use num_complex;
+#[cfg(feature = "ndarray")]
+pub use ndarray;
+#[cfg(feature = "vulkano")]
+pub use host::{fft, ifft, irfft, rfft, Context};
 
 
 
@@ -18,4 +45,20 @@ mod tests {
         println!("version: {:?}", version);
 
     }
+
+    #[test]
+    fn thread_safety() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<config::Config>();
+        assert_send::<app::App>();
+        assert_send::<app::LaunchParams>();
+        assert_send::<app::SharedApp>();
+        assert_send::<nd::NdApp>();
+        assert_send::<future::FftFuture<std::pin::Pin<Box<app::App>>>>();
+        assert_sync::<app::SharedApp>();
+        #[cfg(feature = "vulkano")]
+        assert_sync::<host::Context>();
+    }
 }
@@ -0,0 +1,247 @@
+use std::pin::Pin;
+
+use ash::vk;
+
+use crate::{
+    app::{App, LaunchParams, LaunchParamsBuilder, RecordedFft, Submission},
+    config::ConfigBuilder,
+    error,
+};
+
+/// A transform over any number of axes, split into VkFFT applications of up to three axes each.
+///
+/// The data is packed with `shape[0]` varying fastest. The first stage transforms the first three
+/// axes, batched over the rest. Every later stage transforms the next two axes in place on
+/// `buffer`: the axes done so far become a single omitted dimension, the next two axes are strided
+/// dimensions and the remaining axes are batched over.
+///
+/// For R2C the first stage halves `shape[0]` as configured, and the later stages work on the
+/// complex data in `buffer`. Stages after the first don't support convolution, zero padding or an
+/// output buffer.
+pub struct NdApp {
+    stages: Vec<Pin<Box<App>>>,
+}
+
+impl NdApp {
+    /// Builds the stages from `config`, which provides everything but the shape. `dim`, the strides,
+    /// the omitted dimensions and the batch count are set per stage, where the batch count of
+    /// `config` counts whole N-D arrays.
+    pub fn new(config: ConfigBuilder, shape: &[u64]) -> error::Result<Self> {
+        if shape.is_empty() {
+            return Err(error::Error::EmptyFftDim);
+        }
+
+        if shape.contains(&0) {
+            return Err(error::Error::EmptySize);
+        }
+
+        let batches = config.batches();
+
+        let mut size = [1; 3];
+        for (s, &len) in size.iter_mut().zip(shape) {
+            *s = len;
+        }
+
+        let first = match shape.len() {
+            1 => config.clone().dim(&[size[0]]),
+            2 => config.clone().dim(&[size[0], size[1]]),
+            _ => config.clone().dim(&size),
+        };
+
+        let mut stages = vec![App::new(first.batch_count(outer(shape, 3, batches)).build()?)?];
+
+        if shape.len() > 3 {
+            let complex = config.complex_stage()?;
+            let r2c = stages[0].config.config.performR2C != 0;
+
+            for stage in later_stages(shape, batches, r2c) {
+                let stage = complex
+                    .clone()
+                    .dim(&stage.size)
+                    .omit_dimension(&[true, false, false])
+                    .buffer_stride(&stage.stride)
+                    .batch_count(stage.batches);
+
+                stages.push(App::new(stage.build()?)?);
+            }
+        }
+
+        Ok(Self { stages })
+    }
+
+    pub fn stages(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Records all stages into `params.command_buffer`, with a barrier between consecutive stages.
+    /// The inverse runs the stages in reverse order.
+    ///
+    /// Later stages only get `buffer` and `temp_buffer` of `params`.
+    pub fn launch(&mut self, params: &mut LaunchParams, inverse: bool) -> error::Result<RecordedFft> {
+        let device = self.stages[0].device.clone();
+        let count = self.stages.len();
+
+        let mut recorded: Option<RecordedFft> = None;
+
+        for i in 0..count {
+            let stage = if inverse { count - 1 - i } else { i };
+
+            if i > 0 {
+                barrier(&device, params.command_buffer);
+            }
+
+            let res = if stage == 0 {
+                self.stages[0].launch(params, inverse)
+            } else {
+                let mut complex = LaunchParams {
+                    command_buffer: params.command_buffer,
+                    buffer: params.buffer.clone(),
+                    temp_buffer: params.temp_buffer.clone(),
+                    input_buffer: None,
+                    output_buffer: None,
+                    kernel: None,
+                };
+
+                self.stages[stage].launch(&mut complex, inverse)
+            };
+
+            let res = match res {
+                Ok(res) => res,
+                Err(e) => {
+                    // The command buffer of a failed launch must not be submitted.
+                    if let Some(recorded) = recorded.take() {
+                        unsafe { recorded.assume_completed() };
+                    }
+
+                    return Err(e);
+                }
+            };
+
+            // Every stage records the same command buffer, and the first stage's buffers include
+            // those of all others, so one `RecordedFft` is enough to keep them alive.
+            if stage == 0 {
+                recorded = Some(res);
+            } else {
+                unsafe { res.assume_completed() };
+            }
+        }
+
+        Ok(recorded.expect("there is a first stage"))
+    }
+
+    pub fn forward(&mut self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
+        self.launch(params, false)
+    }
+
+    pub fn inverse(&mut self, params: &mut LaunchParams) -> error::Result<RecordedFft> {
+        self.launch(params, true)
+    }
+
+    /// Like [`App::execute`], using the command pool, queue and fence of the config.
+    pub fn execute(&mut self, params: LaunchParamsBuilder, inverse: bool) -> error::Result<()> {
+        let submission = Submission::new(&self.stages[0]);
+        let recorded = submission.submit(params, |params| self.launch(params, inverse))?;
+
+        submission.wait()?;
+        submission.complete(recorded)
+    }
+
+    pub fn execute_forward(&mut self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, false)
+    }
+
+    pub fn execute_inverse(&mut self, params: LaunchParamsBuilder) -> error::Result<()> {
+        self.execute(params, true)
+    }
+}
+
+/// The size, strides and batch count of a stage after the first.
+struct Stage {
+    size: [u64; 3],
+    stride: [u64; 3],
+    batches: u64,
+}
+
+/// Arrays of `shape[from..]`, times the `batches` whole arrays.
+fn outer(shape: &[u64], from: usize, batches: u64) -> u64 {
+    shape.get(from..).unwrap_or(&[]).iter().product::<u64>() * batches
+}
+
+/// The stages after the first, two axes each.
+fn later_stages(shape: &[u64], batches: u64, r2c: bool) -> Vec<Stage> {
+    // The omitted x dimension covers the axes done so far, with x halved for R2C.
+    let mut done = shape.iter().take(3).product::<u64>();
+
+    if r2c {
+        done = done / shape[0] * (shape[0] / 2 + 1);
+    }
+
+    let mut stages = Vec::new();
+
+    for axis in (3..shape.len()).step_by(2) {
+        let y = shape[axis];
+        let z = shape.get(axis + 1).copied().unwrap_or(1);
+
+        stages.push(Stage {
+            size: [done, y, z],
+            stride: [done, done * y, done * y * z],
+            batches: outer(shape, axis + 2, batches),
+        });
+
+        done *= y * z;
+    }
+
+    stages
+}
+
+/// Makes the writes of one stage visible to the next.
+fn barrier(device: &ash::Device, command_buffer: vk::CommandBuffer) {
+    let barrier = vk::MemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            &[barrier.build()],
+            &[],
+            &[],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(stages: &[Stage]) -> Vec<([u64; 3], [u64; 3], u64)> {
+        stages.iter().map(|s| (s.size, s.stride, s.batches)).collect()
+    }
+
+    #[test]
+    fn four_axes() {
+        // (x, y, z, t): the first stage does x, y and z for every t, the second stage does t.
+        let shape = [8, 4, 2, 3];
+        assert_eq!(outer(&shape, 3, 2), 6);
+        assert_eq!(parts(&later_stages(&shape, 2, false)), [([64, 3, 1], [64, 192, 192], 2)]);
+
+        // R2C leaves 8 / 2 + 1 complex elements per row.
+        assert_eq!(parts(&later_stages(&shape, 2, true)), [([40, 3, 1], [40, 120, 120], 2)]);
+    }
+
+    #[test]
+    fn more_axes() {
+        assert_eq!(parts(&later_stages(&[8, 4, 2, 3, 5], 1, false)), [([64, 3, 5], [64, 192, 960], 1)]);
+
+        assert_eq!(
+            parts(&later_stages(&[8, 4, 2, 3, 5, 7], 2, false)),
+            [([64, 3, 5], [64, 192, 960], 14), ([960, 7, 1], [960, 6720, 6720], 2)],
+        );
+
+        assert!(later_stages(&[8, 4, 2], 1, false).is_empty());
+        assert_eq!(outer(&[8, 4], 3, 5), 5);
+    }
+}
@@ -0,0 +1,133 @@
+use vkfft_src::{VkFFTApplication, VkFFTPlan};
+
+/// What VkFFT decided when creating a plan, read back from the initialized application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlanInfo {
+    /// The axes the plan transforms, `None` for omitted axes and axes beyond `fft_dim`.
+    pub axes: [Option<AxisInfo>; 3],
+
+    /// Primes whose Rader kernels were computed with an FFT, shared by all axes.
+    pub rader_primes: Vec<u64>,
+
+    /// Size in bytes of the temp buffer, `None` if the plan doesn't use one. VkFFT allocates it
+    /// itself unless the config gave one.
+    pub temp_buffer_size: Option<u64>,
+
+    /// Whether VkFFT allocated the temp buffer itself.
+    pub allocated_temp_buffer: bool,
+
+    /// Batches VkFFT runs, including the ones it folded in from the config.
+    pub actual_num_batches: u64,
+
+    /// Threads per workgroup VkFFT aimed for, from the tuning options or its default.
+    pub aim_threads: u64,
+
+    /// Bytes of a coalesced memory transaction VkFFT assumed, from the tuning options or the
+    /// default for the vendor.
+    pub coalesced_memory: u64,
+
+    /// Whether registers extend shared memory for lengths that aren't a power of two.
+    pub register_boost_non_pow2: bool,
+
+    /// Whether the twiddle factors come from lookup tables.
+    pub use_lut: bool,
+
+    /// Whether the twiddle factors between the passes of the four-step algorithm come from lookup
+    /// tables.
+    pub use_lut_4_step: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AxisInfo {
+    /// Length of the sequences the axis transforms, larger than the axis for Bluestein's algorithm.
+    pub size: u64,
+
+    /// Whether the axis is padded to a length VkFFT can transform and uses Bluestein's algorithm.
+    pub bluestein: bool,
+
+    /// Whether some pass handles a prime factor with Rader's algorithm.
+    pub rader: bool,
+
+    /// Whether the sequences don't fit into shared memory and are transformed in several passes,
+    /// i.e. with the four-step algorithm.
+    pub four_step: bool,
+
+    /// Whether the four-step algorithm unshuffles its result, which needs the temp buffer.
+    pub reorder_four_step: bool,
+
+    /// The passes in VkFFT's order, the last of which is dispatched first.
+    pub passes: Vec<PassInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassInfo {
+    /// Length of the sub-sequences transformed in this pass.
+    pub split: u64,
+
+    /// Radices of the stages of the pass.
+    pub radices: Vec<u64>,
+}
+
+impl PlanInfo {
+    /// # Safety
+    ///
+    /// `app` must be initialized by `initializeVkFFT`.
+    pub(crate) unsafe fn new(app: &VkFFTApplication) -> Self {
+        let config = &app.configuration;
+
+        let plan = app.localFFTPlan.as_ref().or(app.localFFTPlan_inverse.as_ref());
+        let dim = (config.FFTdim as usize).min(3);
+
+        let axes = [0, 1, 2].map(|axis| {
+            let plan = plan?;
+            let transformed = axis < dim && config.omitDimension[axis] == 0 && plan.numAxisUploads[axis] > 0;
+            transformed.then(|| AxisInfo::new(app, plan, axis))
+        });
+
+        let uses_temp_buffer = config.userTempBuffer != 0 || config.allocateTempBuffer != 0;
+        let temp_buffer_size = match config.tempBufferSize.as_ref() {
+            Some(&size) if uses_temp_buffer => Some(size),
+            _ => None,
+        };
+
+        Self {
+            axes,
+            rader_primes: app.rader_primes[..(app.numRaderFFTPrimes as usize).min(app.rader_primes.len())].to_vec(),
+            temp_buffer_size,
+            allocated_temp_buffer: config.allocateTempBuffer != 0,
+            actual_num_batches: app.actualNumBatches,
+            aim_threads: config.aimThreads,
+            coalesced_memory: config.coalescedMemory,
+            register_boost_non_pow2: config.registerBoostNonPow2 != 0,
+            use_lut: config.useLUT == 1,
+            use_lut_4_step: config.useLUT_4step == 1,
+        }
+    }
+}
+
+impl AxisInfo {
+    fn new(app: &VkFFTApplication, plan: &VkFFTPlan, axis: usize) -> Self {
+        let uploads = (plan.numAxisUploads[axis] as usize).min(plan.axes[axis].len());
+        let constants = |pass: usize| &plan.axes[axis][pass].specializationConstants;
+
+        let passes = (0..uploads)
+            .map(|pass| {
+                let c = constants(pass);
+
+                PassInfo {
+                    split: plan.axisSplit[axis][pass],
+                    radices: c.stageRadix[..(c.numStages as usize).min(c.stageRadix.len())].to_vec(),
+                }
+            })
+            .collect();
+
+        Self {
+            size: plan.actualFFTSizePerAxis[axis][axis],
+            bluestein: app.useBluesteinFFT[axis] != 0,
+            rader: (0..uploads).any(|pass| constants(pass).useRader != 0),
+            four_step: uploads > 1,
+            reorder_four_step: (0..uploads).any(|pass| constants(pass).reorderFourStep != 0),
+            passes,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use ash::vk;
+
+use crate::error;
+
+/// GPU time spent in a profiled launch, see [`ConfigBuilder::profile`](crate::config::ConfigBuilder::profile).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaunchProfile {
+    /// Time between the end of the previous axis (or the start of the launch) and the end of the
+    /// dispatches of each axis. `None` for axes the launch didn't transform, and for all axes of
+    /// convolutions, which run the inverse of the other axes right after the last one.
+    pub axis_times: [Option<Duration>; 3],
+
+    /// Time of the whole launch.
+    pub total: Duration,
+}
+
+/// One timestamp at the start of a launch and one after each axis.
+const QUERY_COUNT: u32 = 4;
+
+/// The timestamp queries of a profiled [`App`](crate::app::App).
+pub(crate) struct Profiler {
+    pool: vk::QueryPool,
+    period: f32,
+    /// The bits of the timestamps the queue writes, above which they wrap around.
+    valid_bits: u32,
+
+    /// The axes of the last recorded launch in the order their timestamps were written, empty if
+    /// only the whole launch was timed.
+    axes: Vec<usize>,
+    recorded: bool,
+}
+
+impl Profiler {
+    pub(crate) fn new(device: &ash::Device, period: f32, valid_bits: u32) -> error::Result<Self> {
+        let info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(QUERY_COUNT);
+
+        let pool = unsafe { device.create_query_pool(&info, None) }.map_err(|_| error::Error::FailedToCreateQueryPool)?;
+
+        Ok(Self {
+            pool,
+            period,
+            valid_bits,
+            axes: Vec::new(),
+            recorded: false,
+        })
+    }
+
+    /// Resets the queries and writes the timestamp the axes are measured from.
+    pub(crate) fn begin(&mut self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        self.axes.clear();
+        self.recorded = false;
+
+        unsafe {
+            device.cmd_reset_query_pool(command_buffer, self.pool, 0, QUERY_COUNT);
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.pool, 0);
+        }
+    }
+
+    /// Writes the timestamp after the dispatches of `axis`, or of the whole launch if `None`.
+    pub(crate) fn end(&mut self, device: &ash::Device, command_buffer: vk::CommandBuffer, axis: Option<usize>) {
+        let query = self.axes.len() as u32 + 1;
+        self.axes.extend(axis);
+        self.recorded = true;
+
+        unsafe {
+            device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.pool, query);
+        }
+    }
+
+    /// The profile of the last recorded launch, `None` if nothing was recorded or it hasn't finished.
+    pub(crate) fn read(&self, device: &ash::Device) -> error::Result<Option<LaunchProfile>> {
+        if !self.recorded {
+            return Ok(None);
+        }
+
+        let count = self.axes.len().max(1) + 1;
+        let mut ticks = [0u64; QUERY_COUNT as usize];
+
+        let res = unsafe {
+            device.get_query_pool_results(self.pool, 0, count as u32, &mut ticks[..count], vk::QueryResultFlags::TYPE_64)
+        };
+
+        match res {
+            Ok(()) => {}
+            Err(vk::Result::NOT_READY) => return Ok(None),
+            Err(_) => return Err(error::Error::FailedToGetQueryResults),
+        }
+
+        let duration = |from: u64, to: u64| Duration::from_nanos((ticks_between(from, to, self.valid_bits) as f64 * self.period as f64) as u64);
+
+        let mut axis_times = [None; 3];
+        for (i, &axis) in self.axes.iter().enumerate() {
+            axis_times[axis] = Some(duration(ticks[i], ticks[i + 1]));
+        }
+
+        Ok(Some(LaunchProfile {
+            axis_times,
+            total: duration(ticks[0], ticks[count - 1]),
+        }))
+    }
+
+    pub(crate) fn destroy(&mut self, device: &ash::Device) {
+        unsafe {
+            device.destroy_query_pool(self.pool, None);
+        }
+    }
+}
+
+/// Ticks between two timestamps of a queue whose timestamps have `valid_bits` valid bits, i.e. the
+/// `timestamp_valid_bits` of its queue family in `1..=64`. Bits above those are ignored, and a
+/// counter that wrapped around in between is accounted for.
+pub fn ticks_between(from: u64, to: u64, valid_bits: u32) -> u64 {
+    let mask = u64::MAX >> (64 - valid_bits.clamp(1, 64));
+    to.wrapping_sub(from) & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_ticks() {
+        assert_eq!(ticks_between(100, 250, 64), 150);
+        assert_eq!(ticks_between(u64::MAX - 9, 5, 64), 15);
+
+        // A 36 bit counter wraps to zero after 2^36 - 1, and only the low bits of the values are
+        // meaningful.
+        assert_eq!(ticks_between((1 << 36) - 10, 5, 36), 15);
+        assert_eq!(ticks_between(0xff << 36 | 100, 250, 36), 150);
+    }
+}
@@ -0,0 +1,572 @@
+//! A CPU implementation of the transforms VkFFT performs, for testing without a GPU or as a
+//! fallback.
+//!
+//! It follows VkFFT's semantics rather than those of other FFT libraries:
+//!
+//! - The forward transform uses `exp(-2πi jk / n)` and the inverse `exp(2πi jk / n)`. Only the
+//!   inverse is scaled, by the product of the transformed lengths, and only with `normalize`.
+//! - Data is packed with x varying fastest, followed by y, z, the coordinate features and the
+//!   batches. For R2C the complex side halves x to `n / 2 + 1` elements. In place, VkFFT pads the
+//!   real rows to `2 * (n / 2 + 1)` elements, see [`Reference::pad_rows`].
+//! - With zero padding, the forward transform reads the zero block as zeros and the inverse doesn't
+//!   write it. The reference writes zeros there, so comparisons should skip the elements for which
+//!   [`Reference::is_padded`] is true.
+//! - Convolution multiplies the forward transform of the input with a kernel that is already in the
+//!   frequency domain, as made by a `kernel_convolution` plan, and transforms back.
+//! - DCTs are unnormalized like FFTW's `REDFT` kinds, see [`Dct`].
+//!
+//! Everything is computed in double precision. Power of two lengths use a radix-2 FFT, other
+//! lengths Bluestein's algorithm. DCTs are computed directly from their definition.
+//!
+//! [`signal`] makes deterministic input data, and [`Scalar`] converts it to the element types of
+//! the device buffers and back.
+
+use std::f64::consts::PI;
+
+use crate::{
+    config::{Config, Dct},
+    element::RealElement,
+};
+
+/// A complex number as real part followed by imaginary part.
+pub type Complex = [f64; 2];
+
+/// A real element type, converted from and to the `f64` the reference computes in.
+pub trait Scalar: RealElement {
+    /// The name of the precision, e.g. for reports.
+    const NAME: &'static str;
+    /// The relative error transforms in this precision are expected to stay within.
+    const TOLERANCE: f64;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Scalar for f32 {
+    const NAME: &'static str = "single";
+    const TOLERANCE: f64 = 1e-5;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Scalar for f64 {
+    const NAME: &'static str = "double";
+    const TOLERANCE: f64 = 1e-12;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+#[cfg(feature = "half")]
+impl Scalar for half::f16 {
+    const NAME: &'static str = "half";
+    const TOLERANCE: f64 = 1e-2;
+
+    fn from_f64(value: f64) -> Self {
+        half::f16::from_f64(value)
+    }
+
+    fn to_f64(self) -> f64 {
+        half::f16::to_f64(self)
+    }
+}
+
+/// Deterministic data in [-1, 1), the same for a given `len` and `seed` on every platform.
+pub fn signal(len: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+        })
+        .collect()
+}
+
+/// The transform of a plan, without any of its device state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub fft_dim: usize,
+    pub size: [usize; 3],
+    pub omit_dimension: [bool; 3],
+    pub coordinate_features: usize,
+    pub batch_count: usize,
+    pub normalize: bool,
+    pub r2c: bool,
+    pub dct: Option<Dct>,
+    pub zero_padding: [bool; 3],
+    pub zeropad_left: [usize; 3],
+    pub zeropad_right: [usize; 3],
+}
+
+impl Reference {
+    /// A C2C transform over the axes of `size`, like [`ConfigBuilder::dim`](crate::config::ConfigBuilder::dim).
+    pub fn new<const N: usize>(size: &[usize; N]) -> Self {
+        assert!((1..=3).contains(&N));
+
+        let mut res = Self {
+            fft_dim: N,
+            size: [1; 3],
+            omit_dimension: [false; 3],
+            coordinate_features: 1,
+            batch_count: 1,
+            normalize: false,
+            r2c: false,
+            dct: None,
+            zero_padding: [false; 3],
+            zeropad_left: [0; 3],
+            zeropad_right: [0; 3],
+        };
+
+        res.size[..N].copy_from_slice(size);
+        res
+    }
+
+    /// The transform `config` describes.
+    pub fn from_config(config: &Config) -> Self {
+        let usize3 = |v: [u64; 3]| v.map(|x| x as usize);
+
+        Self {
+            fft_dim: config.fft_dim as usize,
+            size: usize3(config.size),
+            omit_dimension: config.omit_dimension,
+            coordinate_features: config.coordinate_features as usize,
+            batch_count: config.batch_count.unwrap_or(1) as usize,
+            normalize: config.normalize,
+            r2c: config.r2c,
+            dct: config.dct,
+            zero_padding: config.zero_padding,
+            zeropad_left: usize3(config.zeropad_left),
+            zeropad_right: usize3(config.zeropad_right),
+        }
+    }
+
+    /// Elements of the whole data, or of its real side for R2C.
+    pub fn elements(&self) -> usize {
+        self.size.iter().product::<usize>() * self.arrays()
+    }
+
+    /// Elements of the complex side of an R2C transform.
+    pub fn complex_elements(&self) -> usize {
+        self.complex_size().iter().product::<usize>() * self.arrays()
+    }
+
+    /// Whether the element at `index` (x, y, z) lies in the zero block of any zero padded axis.
+    pub fn is_padded(&self, index: [usize; 3]) -> bool {
+        (0..3).any(|axis| {
+            self.zero_padding[axis] && (self.zeropad_left[axis]..self.zeropad_right[axis]).contains(&index[axis])
+        })
+    }
+
+    /// The forward or inverse C2C transform of `data`, in place.
+    pub fn fft(&self, data: &mut [Complex], inverse: bool) {
+        assert!(!self.r2c && self.dct.is_none());
+        assert_eq!(data.len(), self.elements());
+
+        if !inverse {
+            self.zero_block(data, self.size);
+        }
+
+        self.transform(data, self.size, 0, inverse);
+
+        if inverse {
+            self.zero_block(data, self.size);
+        }
+    }
+
+    /// The forward R2C transform of `real`.
+    pub fn rfft(&self, real: &[f64]) -> Vec<Complex> {
+        assert!(self.r2c && !self.omit_dimension[0]);
+        assert_eq!(real.len(), self.elements());
+
+        let mut real = real.to_vec();
+        self.zero_block(&mut real, self.size);
+
+        let n = self.size[0];
+        let half = n / 2 + 1;
+        let mut res = Vec::with_capacity(self.complex_elements());
+
+        for row in real.chunks(n) {
+            let mut line: Vec<Complex> = row.iter().map(|&x| [x, 0.0]).collect();
+            fft(&mut line, -1.0);
+            res.extend_from_slice(&line[..half]);
+        }
+
+        self.transform(&mut res, self.complex_size(), 1, false);
+        res
+    }
+
+    /// The inverse C2R transform of the complex side of an R2C transform. The imaginary parts of the
+    /// first element of each row, and of the last one for even lengths, are ignored.
+    pub fn irfft(&self, complex: &[Complex]) -> Vec<f64> {
+        assert!(self.r2c && !self.omit_dimension[0]);
+        assert_eq!(complex.len(), self.complex_elements());
+
+        let mut complex = complex.to_vec();
+        self.transform(&mut complex, self.complex_size(), 1, true);
+
+        let n = self.size[0];
+        let half = n / 2 + 1;
+        let mut res = Vec::with_capacity(self.elements());
+
+        for row in complex.chunks(half) {
+            let mut line = vec![[0.0; 2]; n];
+
+            line[..half].copy_from_slice(row);
+            line[0][1] = 0.0;
+            if n % 2 == 0 {
+                line[n / 2][1] = 0.0;
+            }
+
+            for j in half..n {
+                let [re, im] = line[n - j];
+                line[j] = [re, -im];
+            }
+
+            fft(&mut line, 1.0);
+
+            let scale = self.scale(0);
+            res.extend(line.iter().map(|c| c[0] * scale));
+        }
+
+        self.zero_block(&mut res, self.size);
+        res
+    }
+
+    /// The forward or inverse DCT of `data`, in place. The inverse of a DCT-II is a DCT-III and
+    /// vice versa, and is scaled by `2 * n`, or `2 * (n - 1)` for a DCT-I, when normalized.
+    pub fn dct(&self, data: &mut [f64], inverse: bool) {
+        let dct = self.dct.expect("a DCT type is set");
+        assert!(!self.r2c);
+        assert_eq!(data.len(), self.elements());
+
+        let dct = match (dct, inverse) {
+            (Dct::II, true) => Dct::III,
+            (Dct::III, true) => Dct::II,
+            (dct, _) => dct,
+        };
+
+        for axis in self.axes() {
+            let n = self.size[axis];
+            let scale = match (inverse && self.normalize, dct) {
+                (false, _) => 1.0,
+                (true, Dct::I) => 1.0 / (2 * n.saturating_sub(1)).max(1) as f64,
+                (true, _) => 1.0 / (2 * n) as f64,
+            };
+
+            for_each_line(data, self.size, axis, |line| {
+                let res = dct_direct(line, dct);
+                for (x, y) in line.iter_mut().zip(res) {
+                    *x = y * scale;
+                }
+            });
+        }
+    }
+
+    /// Convolves `data` with `kernel` in place: the forward transform of `data` is multiplied with
+    /// `kernel` and transformed back. `kernel` holds one array per coordinate feature, shared by all
+    /// batches, as the forward transform of the kernel would leave it.
+    pub fn convolve(&self, data: &mut [Complex], kernel: &[Complex]) {
+        self.fft(data, false);
+        multiply(data, kernel);
+        self.fft(data, true);
+    }
+
+    /// Like [`convolve`](Self::convolve) for R2C transforms, with `kernel` in the halved layout.
+    pub fn convolve_real(&self, real: &[f64], kernel: &[Complex]) -> Vec<f64> {
+        let mut complex = self.rfft(real);
+        multiply(&mut complex, kernel);
+        self.irfft(&complex)
+    }
+
+    /// Pads each row of packed `real` data to `2 * (n / 2 + 1)` elements, the layout of the real
+    /// side of an in-place R2C transform.
+    pub fn pad_rows(&self, real: &[f64]) -> Vec<f64> {
+        let n = self.size[0];
+        let padded = 2 * (n / 2 + 1);
+
+        real.chunks(n)
+            .flat_map(|row| row.iter().copied().chain(std::iter::repeat(0.0).take(padded - n)))
+            .collect()
+    }
+
+    /// Inverse of [`pad_rows`](Self::pad_rows).
+    pub fn unpad_rows(&self, padded: &[f64]) -> Vec<f64> {
+        let n = self.size[0];
+
+        padded
+            .chunks(2 * (n / 2 + 1))
+            .flat_map(|row| row[..n].iter().copied())
+            .collect()
+    }
+
+    fn arrays(&self) -> usize {
+        self.coordinate_features * self.batch_count
+    }
+
+    fn complex_size(&self) -> [usize; 3] {
+        [self.size[0] / 2 + 1, self.size[1], self.size[2]]
+    }
+
+    /// The transformed axes.
+    fn axes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.fft_dim.min(3)).filter(|&axis| !self.omit_dimension[axis])
+    }
+
+    /// The normalization of the inverse along `axis`.
+    fn scale(&self, axis: usize) -> f64 {
+        match self.normalize && self.axes().any(|a| a == axis) {
+            true => 1.0 / self.size[axis] as f64,
+            false => 1.0,
+        }
+    }
+
+    /// C2C transforms along the transformed axes from `first` on of data shaped `size`.
+    fn transform(&self, data: &mut [Complex], size: [usize; 3], first: usize, inverse: bool) {
+        let sign = if inverse { 1.0 } else { -1.0 };
+
+        for axis in self.axes().filter(|&axis| axis >= first) {
+            let scale = if inverse { self.scale(axis) } else { 1.0 };
+
+            for_each_line(data, size, axis, |line| {
+                fft(line, sign);
+                for c in line.iter_mut() {
+                    *c = [c[0] * scale, c[1] * scale];
+                }
+            });
+        }
+    }
+
+    /// Zeros the elements of the zero block in data shaped `size`.
+    fn zero_block<T: Default>(&self, data: &mut [T], size: [usize; 3]) {
+        if !self.zero_padding.contains(&true) {
+            return;
+        }
+
+        for (i, value) in data.iter_mut().enumerate() {
+            let index = [i % size[0], i / size[0] % size[1], i / (size[0] * size[1]) % size[2]];
+
+            if self.is_padded(index) {
+                *value = T::default();
+            }
+        }
+    }
+}
+
+/// Calls `f` on a copy of every line of `data` along `axis`, and writes the line back.
+fn for_each_line<T: Copy>(data: &mut [T], size: [usize; 3], axis: usize, mut f: impl FnMut(&mut [T])) {
+    let n = size[axis];
+    let stride: usize = size[..axis].iter().product();
+    let mut line = Vec::with_capacity(n);
+
+    for start in 0..data.len() {
+        if (start / stride) % n != 0 {
+            continue;
+        }
+
+        line.clear();
+        line.extend((0..n).map(|j| data[start + j * stride]));
+        f(&mut line);
+
+        for (j, &value) in line.iter().enumerate() {
+            data[start + j * stride] = value;
+        }
+    }
+}
+
+fn multiply(data: &mut [Complex], kernel: &[Complex]) {
+    assert!(!kernel.is_empty() && data.len() % kernel.len() == 0);
+
+    for (x, k) in data.iter_mut().zip(kernel.iter().cycle()) {
+        *x = mul(*x, *k);
+    }
+}
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]]
+}
+
+/// `exp(sign * 2πi * k / n)`.
+fn twiddle(k: usize, n: usize, sign: f64) -> Complex {
+    let angle = 2.0 * PI * (k % n) as f64 / n as f64;
+    [angle.cos(), sign * angle.sin()]
+}
+
+/// An unnormalized DFT of `line` with the given sign of the exponent.
+fn fft(line: &mut [Complex], sign: f64) {
+    match line.len() {
+        0 | 1 => {}
+        n if n.is_power_of_two() => radix2(line, sign),
+        _ => bluestein(line, sign),
+    }
+}
+
+fn radix2(line: &mut [Complex], sign: f64) {
+    let n = line.len();
+    let bits = n.trailing_zeros();
+
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            line.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let w = twiddle(k, len, sign);
+                let a = line[start + k];
+                let b = mul(line[start + k + len / 2], w);
+
+                line[start + k] = [a[0] + b[0], a[1] + b[1]];
+                line[start + k + len / 2] = [a[0] - b[0], a[1] - b[1]];
+            }
+        }
+
+        len *= 2;
+    }
+}
+
+/// Any length DFT as a convolution with a chirp, using `jk = (j² + k² - (k - j)²) / 2`.
+fn bluestein(line: &mut [Complex], sign: f64) {
+    let n = line.len();
+    let m = (2 * n - 1).next_power_of_two();
+
+    // exp(sign * πi * k² / n), with k² reduced modulo 2n to keep the angle small.
+    let chirp: Vec<Complex> = (0..n).map(|k| twiddle(k * k % (2 * n), 2 * n, sign)).collect();
+    let conj = |c: Complex| [c[0], -c[1]];
+
+    let mut a = vec![[0.0; 2]; m];
+    for k in 0..n {
+        a[k] = mul(line[k], chirp[k]);
+    }
+
+    let mut b = vec![[0.0; 2]; m];
+    b[0] = conj(chirp[0]);
+    for k in 1..n {
+        b[k] = conj(chirp[k]);
+        b[m - k] = conj(chirp[k]);
+    }
+
+    radix2(&mut a, -1.0);
+    radix2(&mut b, -1.0);
+    for (x, y) in a.iter_mut().zip(&b) {
+        *x = mul(*x, *y);
+    }
+    radix2(&mut a, 1.0);
+
+    for k in 0..n {
+        let c = mul(a[k], chirp[k]);
+        line[k] = [c[0] / m as f64, c[1] / m as f64];
+    }
+}
+
+fn dct_direct(line: &[f64], dct: Dct) -> Vec<f64> {
+    let n = line.len();
+    let cos = |x: f64| (PI * x).cos();
+
+    (0..n)
+        .map(|k| {
+            let k = k as f64;
+            match dct {
+                Dct::I if n == 1 => line[0],
+                Dct::I => {
+                    let last = if k as usize % 2 == 0 { line[n - 1] } else { -line[n - 1] };
+                    let inner: f64 = (1..n - 1).map(|j| line[j] * cos(j as f64 * k / (n - 1) as f64)).sum();
+                    line[0] + last + 2.0 * inner
+                }
+                Dct::II => 2.0 * (0..n).map(|j| line[j] * cos((j as f64 + 0.5) * k / n as f64)).sum::<f64>(),
+                Dct::III => {
+                    let inner: f64 = (1..n).map(|j| line[j] * cos(j as f64 * (k + 0.5) / n as f64)).sum();
+                    line[0] + 2.0 * inner
+                }
+                Dct::IV => {
+                    2.0 * (0..n)
+                        .map(|j| line[j] * cos((j as f64 + 0.5) * (k + 0.5) / n as f64))
+                        .sum::<f64>()
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(line: &[Complex], sign: f64) -> Vec<Complex> {
+        let n = line.len();
+
+        (0..n)
+            .map(|k| {
+                line.iter().enumerate().fold([0.0; 2], |acc, (j, &x)| {
+                    let y = mul(x, twiddle(j * k, n, sign));
+                    [acc[0] + y[0], acc[1] + y[1]]
+                })
+            })
+            .collect()
+    }
+
+    fn complex(values: &[f64]) -> Vec<Complex> {
+        values.chunks_exact(2).map(|c| [c[0], c[1]]).collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn matches_dft() {
+        for n in [1, 2, 8, 12, 17, 64, 100] {
+            let input = complex(&signal(2 * n, n as u64));
+            let mut output = input.clone();
+            fft(&mut output, -1.0);
+
+            assert_close(output.concat().as_slice(), naive(&input, -1.0).concat().as_slice());
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut plan = Reference::new(&[6, 5, 4]);
+        plan.normalize = true;
+        plan.batch_count = 2;
+
+        let input = complex(&signal(2 * plan.elements(), 1));
+        let mut data = input.clone();
+        plan.fft(&mut data, false);
+        plan.fft(&mut data, true);
+        assert_close(data.concat().as_slice(), input.concat().as_slice());
+
+        plan.r2c = true;
+        let real: Vec<f64> = input.iter().map(|c| c[0]).collect();
+        let complex = plan.rfft(&real);
+        assert_eq!(complex.len(), 4 * 5 * 4 * 2);
+        assert_close(&plan.irfft(&complex), &real);
+
+        plan.r2c = false;
+        for dct in [Dct::I, Dct::II, Dct::III, Dct::IV] {
+            plan.dct = Some(dct);
+            let mut data = real.clone();
+            plan.dct(&mut data, false);
+            plan.dct(&mut data, true);
+            assert_close(&data, &real);
+        }
+    }
+}
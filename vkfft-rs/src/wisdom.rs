@@ -0,0 +1,246 @@
+//! Tunings and compiled plans remembered across processes, like FFTW's wisdom.
+//!
+//! [`autotune`](crate::autotune::autotune) records the tuning it found into [`Wisdom::global`], as
+//! does every [`App`](crate::app::App) whose tuning came from a tuning file or the wisdom, or whose
+//! config asked for it with [`record_wisdom`](crate::config::ConfigBuilder::record_wisdom). With
+//! [`save_applications`](Wisdom::save_applications) those plans also keep the binaries VkFFT
+//! compiled. Configs built later without an explicit tuning take the tuning from there, and
+//! matching plans load the binaries instead of compiling their shaders again.
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use vulkano::device::physical::PhysicalDevice;
+
+use crate::{
+    autotune::{device_id, TuningError},
+    config::{Config, TuningOptions},
+};
+
+/// What a plan was built with, on which device.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WisdomEntry {
+    /// The device UUID in hex, or `vendor:device` IDs if the driver doesn't report one.
+    pub device: String,
+    pub driver_version: u32,
+    /// The VkFFT version that compiled `application`.
+    pub vkfft_version: String,
+    /// The parameters of the plan other than its tuning.
+    pub plan: String,
+    pub tuning: TuningOptions,
+    /// The binaries VkFFT saved for the plan, in hex.
+    pub application: Option<String>,
+}
+
+/// Why [`Wisdom::import`] skipped an entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The entry comes from another device.
+    Device,
+    /// The entry comes from another driver version, which may compile the plan differently.
+    DriverVersion,
+    /// The entry comes from another VkFFT version, which may not load its binaries.
+    VkfftVersion,
+}
+
+/// The outcome of [`Wisdom::import`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// Number of entries added.
+    pub added: usize,
+    /// The entries left out, and why.
+    pub skipped: Vec<(WisdomEntry, SkipReason)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Wisdom {
+    pub entries: Vec<WisdomEntry>,
+
+    /// Also keep the binaries of new plans, which makes the exported wisdom much larger.
+    #[serde(skip)]
+    pub save_applications: bool,
+}
+
+static GLOBAL: Mutex<Wisdom> = Mutex::new(Wisdom::new());
+
+/// Identifies a plan on a device.
+pub(crate) struct PlanKey {
+    device: String,
+    driver_version: u32,
+    plan: String,
+}
+
+impl PlanKey {
+    /// `None` for configs without a vulkano physical device.
+    pub(crate) fn new(config: &Config) -> Option<Self> {
+        let physical_device: &PhysicalDevice = config.keep_alive.physical_device.as_ref()?;
+
+        let sizes = [
+            &config.buffer,
+            &config.temp_buffer,
+            &config.input_buffer,
+            &config.output_buffer,
+            &config.kernel,
+        ]
+        .map(|b| b.as_ref().map(|b| b.size()));
+
+        let plan = format!(
+            "size={:?} dim={} batches={:?} precision={:?} r2c={} dct={:?} normalize={} \
+             zero_padding={:?} zeropad={:?}..{:?} omit={:?} strides={:?}/{:?}/{:?} \
+             convolution={}/{}/{} features={} reorder={} lut={} formatted={:?}/{:?} \
+             return_to_input={} algorithm={:?} buffers={:?}",
+            config.size,
+            config.fft_dim,
+            config.batch_count,
+            config.precision,
+            config.r2c,
+            config.dct,
+            config.normalize,
+            config.zero_padding,
+            config.zeropad_left,
+            config.zeropad_right,
+            config.omit_dimension,
+            config.buffer_stride,
+            config.input_buffer_stride,
+            config.output_buffer_stride,
+            config.kernel_convolution,
+            config.convolution,
+            config.symmetric_kernel,
+            config.coordinate_features,
+            !config.disable_reorder_four_step,
+            config.use_lut,
+            config.input_formatted,
+            config.output_formatted,
+            config.inverse_return_to_input_buffer,
+            config.algorithm,
+            sizes,
+        );
+
+        Some(Self {
+            device: device_id(physical_device),
+            driver_version: physical_device.properties().driver_version,
+            plan,
+        })
+    }
+}
+
+impl Wisdom {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            save_applications: false,
+        }
+    }
+
+    /// The wisdom of this process, which plans record into and configs read from.
+    pub fn global() -> MutexGuard<'static, Wisdom> {
+        GLOBAL.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The entries as JSON.
+    pub fn export(&self) -> Result<String, TuningError> {
+        serde_json::to_string_pretty(self).map_err(TuningError::Json)
+    }
+
+    pub fn export_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), TuningError> {
+        std::fs::write(path, self.export()?).map_err(TuningError::Io)
+    }
+
+    /// Adds the entries of exported wisdom for `physical_device`, replacing entries of the same
+    /// plan. Entries from another device, driver version or VkFFT version are skipped and returned
+    /// in the report.
+    pub fn import(&mut self, json: &str, physical_device: &PhysicalDevice) -> Result<ImportReport, TuningError> {
+        let imported: Wisdom = serde_json::from_str(json).map_err(TuningError::Json)?;
+        let device = device_id(physical_device);
+        let driver_version = physical_device.properties().driver_version;
+        let version = crate::version().to_string();
+        let mut report = ImportReport::default();
+
+        for entry in imported.entries {
+            let reason = if entry.device != device {
+                Some(SkipReason::Device)
+            } else if entry.driver_version != driver_version {
+                Some(SkipReason::DriverVersion)
+            } else if entry.vkfft_version != version {
+                Some(SkipReason::VkfftVersion)
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                report.skipped.push((entry, reason));
+                continue;
+            }
+
+            self.insert(entry);
+            report.added += 1;
+        }
+
+        Ok(report)
+    }
+
+    pub fn import_from_file<P: AsRef<Path>>(&mut self, path: P, physical_device: &PhysicalDevice) -> Result<ImportReport, TuningError> {
+        self.import(&std::fs::read_to_string(path).map_err(TuningError::Io)?, physical_device)
+    }
+
+    /// Adds `entry`, replacing the entry of the same plan on the same device.
+    pub fn insert(&mut self, entry: WisdomEntry) {
+        self.entries.retain(|e| (&e.device, &e.plan) != (&entry.device, &entry.plan));
+        self.entries.push(entry);
+    }
+
+    /// The entry of the plan, `None` if it comes from another driver version. Recording the plan
+    /// again replaces such an entry.
+    pub(crate) fn get(&self, key: &PlanKey) -> Option<&WisdomEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.device == key.device && e.plan == key.plan)
+            .filter(|e| e.driver_version == key.driver_version)
+    }
+
+    /// Takes the tuning of `config` from the entry of its plan, if there is one, and records the
+    /// plan again once it is created.
+    pub(crate) fn tune(&self, mut config: Config) -> Config {
+        if let Some(entry) = PlanKey::new(&config).and_then(|key| self.get(&key)) {
+            config.tuning = entry.tuning;
+            config.record_wisdom = true;
+        }
+
+        config
+    }
+
+    /// The saved binaries of the plan, if they were compiled with `tuning`.
+    pub(crate) fn application(&self, key: &PlanKey, tuning: &TuningOptions) -> Option<Vec<u8>> {
+        let entry = self.get(key).filter(|e| &e.tuning == tuning)?;
+        from_hex(entry.application.as_ref()?)
+    }
+
+    pub(crate) fn record(&mut self, key: PlanKey, tuning: TuningOptions, application: Option<&[u8]>) {
+        // Plans that loaded their binaries keep them.
+        let application = match application {
+            Some(application) => Some(to_hex(application)),
+            None => self.get(&key).filter(|e| e.tuning == tuning).and_then(|e| e.application.clone()),
+        };
+
+        self.insert(WisdomEntry {
+            device: key.device,
+            driver_version: key.driver_version,
+            vkfft_version: crate::version().to_string(),
+            plan: key.plan,
+            tuning,
+            application,
+        });
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,940 @@
+//! Runs plans on a software Vulkan implementation, Mesa's lavapipe or SwiftShader, and compares the
+//! results with the CPU reference. This covers the configs VkFFT sees through `App::new` and
+//! `App::launch` on machines without a GPU.
+//!
+//! The tests pick the first device of type CPU. Without one they print a note and pass without
+//! running anything. Double precision is skipped on devices without `shaderFloat64`.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use vkfft_rs::{
+    app::{App, BufferKind, LaunchError, LaunchParams, SharedApp},
+    config::{AlgorithmOptions, Buffer, BuildError, Config, ConfigBuilder, TuningOptions},
+    error::Error,
+    reference::{signal, Complex, Reference, Scalar},
+};
+use vulkano::{
+    buffer::{BufferUsage, CpuAccessibleBuffer},
+    command_buffer::pool::{UnsafeCommandPool, UnsafeCommandPoolCreateInfo},
+    device::{physical::PhysicalDeviceType, Device, DeviceCreateInfo, Features, Queue, QueueCreateInfo},
+    instance::{Instance, InstanceCreateInfo},
+    VulkanLibrary,
+};
+
+struct Gpu {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    double: bool,
+}
+
+fn gpu() -> Option<Gpu> {
+    let gpu = software_device();
+
+    if gpu.is_none() {
+        eprintln!("no software Vulkan device found, skipping");
+    }
+
+    gpu
+}
+
+fn software_device() -> Option<Gpu> {
+    let library = VulkanLibrary::new().ok()?;
+    let instance = Instance::new(
+        library,
+        InstanceCreateInfo {
+            enumerate_portability: true,
+            ..Default::default()
+        },
+    )
+    .ok()?;
+
+    let physical = instance
+        .enumerate_physical_devices()
+        .ok()?
+        .find(|p| p.properties().device_type == PhysicalDeviceType::Cpu)?;
+
+    let queue_family_index = physical
+        .queue_family_properties()
+        .iter()
+        .position(|q| q.queue_flags.compute)? as u32;
+
+    let double = physical.supported_features().shader_float64;
+
+    let (device, mut queues) = Device::new(
+        physical,
+        DeviceCreateInfo {
+            enabled_features: Features {
+                shader_float64: double,
+                ..Features::empty()
+            },
+            queue_create_infos: vec![QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    )
+    .ok()?;
+
+    Some(Gpu {
+        device,
+        queue: queues.next()?,
+        double,
+    })
+}
+
+/// Runs the single precision test and, where supported, the double precision one.
+fn each_precision(single: fn(&Gpu), double: fn(&Gpu)) {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    single(&gpu);
+
+    if gpu.double {
+        double(&gpu);
+    }
+}
+
+impl Gpu {
+    fn config(&self, size: &[u64]) -> ConfigBuilder {
+        let command_pool = UnsafeCommandPool::new(
+            self.device.clone(),
+            UnsafeCommandPoolCreateInfo {
+                queue_family_index: self.queue.queue_family_index(),
+                reset_command_buffer: true,
+                ..Default::default()
+            },
+        )
+        .expect("command pool");
+
+        // Each plan gets its own pool, which is only used through the `App` owning it.
+        #[allow(clippy::arc_with_non_send_sync)]
+        let command_pool = Arc::new(command_pool);
+
+        let builder = Config::builder()
+            .physical_device(self.device.physical_device().clone())
+            .device(self.device.clone())
+            .queue(self.queue.clone())
+            .command_pool(command_pool);
+
+        match *size {
+            [x] => builder.dim(&[x]),
+            [x, y] => builder.dim(&[x, y]),
+            [x, y, z] => builder.dim(&[x, y, z]),
+            _ => panic!("unsupported size {:?}", size),
+        }
+    }
+
+    fn upload<T: Scalar>(&self, data: &[f64]) -> Arc<CpuAccessibleBuffer<[T]>> {
+        let usage = BufferUsage {
+            storage_buffer: true,
+            ..BufferUsage::empty()
+        };
+
+        CpuAccessibleBuffer::from_iter(self.device.clone(), usage, false, data.iter().map(|&x| T::from_f64(x)))
+            .expect("buffer")
+    }
+}
+
+fn download<T: Scalar>(buffer: &CpuAccessibleBuffer<[T]>) -> Vec<f64> {
+    buffer.read().expect("mapped buffer").iter().map(|&x| x.to_f64()).collect()
+}
+
+fn complex(data: &[f64]) -> Vec<Complex> {
+    data.chunks(2).map(|c| [c[0], c[1]]).collect()
+}
+
+/// Compares the elements for which `check` is true.
+fn compare<T: Scalar>(name: &str, actual: &[f64], expected: &[f64], check: impl Fn(usize) -> bool) {
+    assert_eq!(actual.len(), expected.len(), "{}: length", name);
+
+    let scale = expected.iter().fold(f64::MIN_POSITIVE, |m, x| m.max(x.abs()));
+    let error = actual
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|&(i, _)| check(i))
+        .fold(0.0, |m: f64, (_, (a, e))| m.max((a - e).abs()));
+
+    assert!(
+        error / scale <= T::TOLERANCE,
+        "{}: relative error {:e} exceeds {:e}",
+        name,
+        error / scale,
+        T::TOLERANCE
+    );
+}
+
+fn reference(size: &[u64], batch_count: u64) -> Reference {
+    let mut res = Reference::new(&[1, 1, 1]);
+    res.fft_dim = size.len();
+    res.batch_count = batch_count as usize;
+    res.normalize = true;
+
+    for (s, &len) in res.size.iter_mut().zip(size) {
+        *s = len as usize;
+    }
+
+    res
+}
+
+const C2C_SIZES: &[(&[u64], u64)] = &[
+    (&[8], 1),
+    (&[17], 3),
+    (&[1000], 1),
+    (&[64, 12], 2),
+    (&[9, 10, 11], 1),
+];
+
+fn c2c<T: Scalar>(gpu: &Gpu) {
+    for &(size, batches) in C2C_SIZES {
+        let cpu = reference(size, batches);
+        let input = signal(2 * cpu.elements(), 1);
+        let buffer = gpu.upload::<T>(&input);
+
+        let config = gpu
+            .config(size)
+            .buffer(Buffer::typed(buffer.clone()))
+            .batch_count(batches)
+            .normalize()
+            .build()
+            .expect("config");
+
+        assert_eq!(config.precision(), T::PRECISION);
+        let mut app = App::new(config).expect("app");
+
+        let mut expected = complex(&input);
+        cpu.fft(&mut expected, false);
+
+        app.execute_forward(LaunchParams::builder()).expect("forward");
+        compare::<T>(&format!("c2c {:?} forward", size), &download(&buffer), &expected.concat(), |_| true);
+
+        app.execute_inverse(LaunchParams::builder()).expect("inverse");
+        compare::<T>(&format!("c2c {:?} inverse", size), &download(&buffer), &input, |_| true);
+    }
+}
+
+#[test]
+fn c2c_matches_reference() {
+    each_precision(c2c::<f32>, c2c::<f64>);
+}
+
+/// Polls `future` on this thread until it resolves.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn async_launches_resolve() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let size: &[u64] = &[64];
+    let cpu = reference(size, 1);
+
+    let launches: Vec<_> = (0..4)
+        .map(|seed| {
+            let input = signal(2 * cpu.elements(), 20 + seed);
+            let buffer = gpu.upload::<f32>(&input);
+            let config = gpu.config(size).buffer(Buffer::typed(buffer.clone())).build().expect("config");
+
+            let future = App::new(config)
+                .expect("app")
+                .into_execute_async(LaunchParams::builder(), false)
+                .expect("launch");
+
+            (input, buffer, future)
+        })
+        .collect();
+
+    // All launches are in flight at once, waited on by the same helper thread.
+    for (input, buffer, future) in launches.into_iter().rev() {
+        let mut app = block_on(future).expect("forward");
+
+        let mut expected = complex(&input);
+        cpu.fft(&mut expected, false);
+        compare::<f32>("async forward", &download(&buffer), &expected.concat(), |_| true);
+
+        // The plan comes back ready for the next launch.
+        block_on(app.execute_inverse_async(LaunchParams::builder()).expect("launch")).expect("inverse");
+    }
+}
+
+#[test]
+fn converted_buffers_infer_precision() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f64>(&signal(2 * 8, 18));
+    let config = gpu.config(&[8]).buffer(buffer).build().expect("config");
+
+    assert_eq!(config.precision(), vkfft_rs::config::Precision::Double);
+}
+
+const R2C_SIZES: &[&[u64]] = &[&[16], &[15, 6], &[10, 4, 3], &[250, 3]];
+
+fn r2c<T: Scalar>(gpu: &Gpu) {
+    for &size in R2C_SIZES {
+        let mut cpu = reference(size, 1);
+        cpu.r2c = true;
+
+        let real = signal(cpu.elements(), 2);
+        let buffer = gpu.upload::<T>(&cpu.pad_rows(&real));
+
+        let config = gpu
+            .config(size)
+            .buffer(Buffer::typed(buffer.clone()))
+            .r2c()
+            .normalize()
+            .build()
+            .expect("config");
+
+        let mut app = App::new(config).expect("app");
+
+        app.execute_forward(LaunchParams::builder()).expect("forward");
+        let spectrum = download(&buffer);
+        compare::<T>(&format!("r2c {:?} forward", size), &spectrum, &cpu.rfft(&real).concat(), |_| true);
+
+        app.execute_inverse(LaunchParams::builder()).expect("inverse");
+        let expected = cpu.irfft(&complex(&spectrum));
+        compare::<T>(&format!("r2c {:?} inverse", size), &cpu.unpad_rows(&download(&buffer)), &expected, |_| true);
+    }
+}
+
+#[test]
+fn r2c_matches_reference() {
+    each_precision(r2c::<f32>, r2c::<f64>);
+}
+
+const CONVOLUTION_SIZES: &[&[u64]] = &[&[12], &[32, 8], &[6, 5, 4]];
+
+fn convolution<T: Scalar>(gpu: &Gpu) {
+    for &size in CONVOLUTION_SIZES {
+        let cpu = reference(size, 1);
+        let input = signal(2 * cpu.elements(), 3);
+        let kernel_input = signal(2 * cpu.elements(), 4);
+
+        // The kernel plan leaves the forward transform of the kernel in place.
+        let kernel = gpu.upload::<T>(&kernel_input);
+        let config = gpu
+            .config(size)
+            .buffer(Buffer::typed(kernel.clone()))
+            .kernel_convolution()
+            .build()
+            .expect("kernel config");
+
+        App::new(config)
+            .expect("kernel app")
+            .execute_forward(LaunchParams::builder())
+            .expect("kernel");
+
+        let buffer = gpu.upload::<T>(&input);
+        let config = gpu
+            .config(size)
+            .buffer(Buffer::typed(buffer.clone()))
+            .kernel(Buffer::typed(kernel.clone()))
+            .convolution()
+            .normalize()
+            .build()
+            .expect("convolution config");
+
+        App::new(config)
+            .expect("convolution app")
+            .execute_forward(LaunchParams::builder())
+            .expect("convolution");
+
+        let mut kernel_spectrum = complex(&kernel_input);
+        cpu.fft(&mut kernel_spectrum, false);
+        compare::<T>(&format!("kernel {:?}", size), &download(&kernel), &kernel_spectrum.concat(), |_| true);
+
+        let mut expected = complex(&input);
+        cpu.convolve(&mut expected, &kernel_spectrum);
+        compare::<T>(&format!("convolution {:?}", size), &download(&buffer), &expected.concat(), |_| true);
+    }
+}
+
+#[test]
+fn convolution_matches_reference() {
+    each_precision(convolution::<f32>, convolution::<f64>);
+}
+
+#[test]
+fn convolution_needs_kernel() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 12, 16));
+    let kernel = gpu.upload::<f32>(&signal(2 * 12, 17));
+
+    // `convolution` must reach VkFFT as `performConvolution`, which makes the kernel required.
+    let config = gpu
+        .config(&[12])
+        .buffer(Buffer::typed(buffer.clone()))
+        .kernel(12 * 8)
+        .convolution()
+        .build()
+        .expect("config");
+
+    let mut app = App::new(config).expect("app");
+    let res = app.execute_forward(LaunchParams::builder());
+
+    assert!(matches!(res, Err(Error::Launch(LaunchError::MissingBuffer(BufferKind::Kernel)))));
+
+    app.execute_forward(LaunchParams::builder().kernel(Buffer::typed(kernel))).expect("convolution");
+}
+
+#[test]
+fn failed_launch_releases_buffers() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 64, 12));
+    let config = gpu.config(&[64]).buffer(64 * 8).build().expect("config");
+    let mut app = App::new(config).expect("app");
+
+    // The plan is in-place, so it has no use for an input buffer.
+    let res = app.execute_forward(
+        LaunchParams::builder()
+            .buffer(Buffer::typed(buffer.clone()))
+            .input_buffer(Buffer::typed(buffer.clone())),
+    );
+
+    assert!(res.is_err());
+    assert_eq!(Arc::strong_count(&buffer), 1);
+}
+
+#[test]
+fn recorded_fft_releases_buffers() {
+    use vulkano::command_buffer::{
+        sys::{CommandBufferBeginInfo, UnsafeCommandBufferBuilder},
+        CommandBufferUsage,
+    };
+
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 64, 23));
+    let config = gpu.config(&[64]).buffer(64 * 8).build().expect("config");
+    let mut app = App::new(config).expect("app");
+
+    let pool = UnsafeCommandPool::new(
+        gpu.device.clone(),
+        UnsafeCommandPoolCreateInfo {
+            queue_family_index: gpu.queue.queue_family_index(),
+            ..Default::default()
+        },
+    )
+    .expect("command pool");
+
+    let alloc = pool
+        .allocate_command_buffers(Default::default())
+        .expect("command buffer")
+        .next()
+        .expect("one command buffer");
+
+    let begin_info = CommandBufferBeginInfo {
+        usage: CommandBufferUsage::OneTimeSubmit,
+        ..Default::default()
+    };
+    let command_buffer = unsafe { UnsafeCommandBufferBuilder::new(&alloc, begin_info) }.expect("begin");
+
+    let mut params = LaunchParams::builder()
+        .command_buffer(&command_buffer)
+        .buffer(Buffer::typed(buffer.clone()))
+        .build()
+        .expect("params");
+
+    let recorded = app.forward(&mut params).expect("launch");
+    drop(params);
+    assert!(Arc::strong_count(&buffer) > 1);
+
+    // The command buffer is never submitted, so a future of the device is enough.
+    recorded.release(vulkano::sync::now(gpu.device.clone())).expect("release");
+    assert_eq!(Arc::strong_count(&buffer), 1);
+}
+
+#[test]
+fn launch_checks_in_place_layout() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 64, 14));
+    let input = gpu.upload::<f32>(&signal(2 * 64, 15));
+
+    let config = gpu.config(&[64]).buffer(64 * 8).build().expect("in-place config");
+    let res = App::new(config).expect("in-place app").execute_forward(
+        LaunchParams::builder()
+            .buffer(Buffer::typed(buffer.clone()))
+            .input_buffer(Buffer::typed(input.clone())),
+    );
+
+    assert!(matches!(res, Err(Error::Launch(LaunchError::UnusedBuffer(BufferKind::InputBuffer)))));
+
+    let config = gpu
+        .config(&[64])
+        .buffer(64 * 8)
+        .input_buffer(64 * 8)
+        .input_formatted(true)
+        .build()
+        .expect("out-of-place config");
+
+    let mut app = App::new(config).expect("out-of-place app");
+    let res = app.execute_forward(LaunchParams::builder().buffer(Buffer::typed(buffer.clone())));
+
+    assert!(matches!(res, Err(Error::Launch(LaunchError::MissingBuffer(BufferKind::InputBuffer)))));
+
+    app.execute_forward(
+        LaunchParams::builder()
+            .buffer(Buffer::typed(buffer))
+            .input_buffer(Buffer::typed(input)),
+    )
+    .expect("out-of-place launch");
+}
+
+#[test]
+fn shared_app_binds_buffers() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let bound = gpu.upload::<f32>(&signal(2 * 64, 21));
+    let other = gpu.upload::<f32>(&signal(2 * 64, 22));
+
+    let config = gpu.config(&[64]).buffer(64 * 8).build().expect("config");
+    let app = SharedApp::with_buffers(config, LaunchParams::builder().buffer(Buffer::typed(bound.clone()))).expect("app");
+
+    app.execute_forward(LaunchParams::builder().buffer(Buffer::typed(bound)))
+        .expect("bound buffer");
+
+    // Another buffer would rewrite the descriptor sets under launches recorded by other threads.
+    let res = app.execute_forward(LaunchParams::builder().buffer(Buffer::typed(other)));
+    assert!(matches!(res, Err(Error::Launch(LaunchError::BufferMismatch(BufferKind::Buffer)))));
+
+    let config = gpu.config(&[64]).buffer(64 * 8).build().expect("config");
+    let res = SharedApp::with_buffers(config, LaunchParams::builder());
+    assert!(matches!(res, Err(Error::Launch(LaunchError::MissingBuffer(BufferKind::Buffer)))));
+}
+
+fn zero_padding<T: Scalar>(gpu: &Gpu) {
+    let size: &[u64] = &[64, 8];
+
+    let mut cpu = reference(size, 1);
+    cpu.zero_padding = [true, true, false];
+    cpu.zeropad_left = [40, 6, 0];
+    cpu.zeropad_right = [64, 8, 0];
+
+    // Garbage in the zero block, which the forward transform must not read.
+    let input = signal(2 * cpu.elements(), 5);
+    let buffer = gpu.upload::<T>(&input);
+
+    let config = gpu
+        .config(size)
+        .buffer(Buffer::typed(buffer.clone()))
+        .zero_padding(&[true, true])
+        .zeropad_left(&[40, 6])
+        .zeropad_right(&[64, 8])
+        .normalize()
+        .build()
+        .expect("config");
+
+    let mut app = App::new(config).expect("app");
+
+    let mut expected = complex(&input);
+    cpu.fft(&mut expected, false);
+
+    app.execute_forward(LaunchParams::builder()).expect("forward");
+    compare::<T>("zero padding forward", &download(&buffer), &expected.concat(), |_| true);
+
+    // The inverse doesn't write the zero block.
+    cpu.fft(&mut expected, true);
+    let x = cpu.size[0];
+    let outside = |i: usize| !cpu.is_padded([i / 2 % x, i / 2 / x, 0]);
+
+    app.execute_inverse(LaunchParams::builder()).expect("inverse");
+    compare::<T>("zero padding inverse", &download(&buffer), &expected.concat(), outside);
+}
+
+#[test]
+fn zero_padding_matches_reference() {
+    each_precision(zero_padding::<f32>, zero_padding::<f64>);
+}
+
+fn profile<T: Scalar>(gpu: &Gpu) {
+    let size: &[u64] = &[64, 12];
+
+    let cpu = reference(size, 1);
+    let input = signal(2 * cpu.elements(), 6);
+    let buffer = gpu.upload::<T>(&input);
+
+    let config = gpu
+        .config(size)
+        .buffer(Buffer::typed(buffer.clone()))
+        .profile()
+        .build()
+        .expect("config");
+
+    let mut app = App::new(config).expect("app");
+    assert_eq!(app.profile().expect("profile"), None);
+
+    let mut expected = complex(&input);
+    cpu.fft(&mut expected, false);
+
+    // Splitting the launch into one append per axis must not change the result.
+    app.execute_forward(LaunchParams::builder()).expect("forward");
+    compare::<T>("profiled forward", &download(&buffer), &expected.concat(), |_| true);
+
+    let profile = app.profile().expect("profile").expect("finished launch");
+    assert!(profile.axis_times[0].is_some() && profile.axis_times[1].is_some());
+    assert_eq!(profile.axis_times[2], None);
+    assert!(profile.axis_times.iter().flatten().sum::<std::time::Duration>() <= profile.total);
+}
+
+#[test]
+fn profile_needs_timestamps() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 64, 19));
+    let config = gpu.config(&[64]).buffer(Buffer::typed(buffer));
+
+    // The valid bits come from the queue family unless given.
+    let built = config.clone().profile().build();
+    let supported = gpu
+        .device
+        .physical_device()
+        .queue_family_properties()[gpu.queue.queue_family_index() as usize]
+        .timestamp_valid_bits;
+    assert_eq!(built.is_ok(), supported.is_some());
+
+    let res = config.profile().timestamp_valid_bits(0).build();
+    assert!(matches!(res, Err(BuildError::NoTimestampSupport)));
+}
+
+#[test]
+fn profile_times_each_axis() {
+    each_precision(profile::<f32>, profile::<f64>);
+}
+
+#[test]
+fn plan_info_describes_axes() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 64 * 12, 7));
+    let config = gpu
+        .config(&[64, 12])
+        .buffer(Buffer::typed(buffer))
+        .build()
+        .expect("config");
+
+    let info = App::new(config).expect("app").plan_info();
+
+    for (axis, size) in [(0, 64), (1, 12)] {
+        let axis = info.axes[axis].as_ref().expect("transformed axis");
+        assert_eq!(axis.size, size);
+        assert!(!axis.bluestein);
+        assert_eq!(axis.passes.iter().map(|p| p.split).product::<u64>(), size);
+        assert_eq!(axis.passes.iter().flat_map(|p| &p.radices).product::<u64>(), size);
+    }
+
+    assert_eq!(info.axes[2], None);
+    assert_eq!(info.actual_num_batches, 1);
+}
+
+#[test]
+fn debug_info_keeps_shaders() {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 32 * 6, 8));
+    let config = gpu
+        .config(&[32, 6])
+        .buffer(Buffer::typed(buffer))
+        .debug_info()
+        .build()
+        .expect("config");
+
+    let mut app = App::new(config).expect("app");
+    app.execute_forward(LaunchParams::builder()).expect("forward");
+
+    let info = app.debug_info().expect("debug info");
+
+    for axis in 0..2 {
+        let shader = info
+            .shaders
+            .iter()
+            .find(|s| !s.inverse && s.axis == axis)
+            .expect("shader of axis");
+
+        assert!(shader.code.contains("void main"));
+        assert_eq!((shader.read, shader.write), (Some(BufferKind::Buffer), Some(BufferKind::Buffer)));
+    }
+}
+
+fn forced_bluestein<T: Scalar>(gpu: &Gpu) {
+    let size: &[u64] = &[1031];
+
+    let cpu = reference(size, 1);
+    let input = signal(2 * cpu.elements(), 9);
+    let buffer = gpu.upload::<T>(&input);
+
+    // Rader's algorithm would handle the prime otherwise.
+    let algorithm = AlgorithmOptions::new()
+        .fix_max_rader_prime_mult(17)
+        .fix_max_rader_prime_fft(17)
+        .force_bluestein_sequence_size(4096);
+
+    let config = gpu
+        .config(size)
+        .buffer(Buffer::typed(buffer.clone()))
+        .algorithm(algorithm)
+        .build()
+        .expect("config");
+
+    let mut app = App::new(config).expect("app");
+
+    let axis = app.plan_info().axes[0].clone().expect("transformed axis");
+    assert!(axis.bluestein);
+    assert_eq!(axis.size, 4096);
+
+    let mut expected = complex(&input);
+    cpu.fft(&mut expected, false);
+
+    app.execute_forward(LaunchParams::builder()).expect("forward");
+    compare::<T>("forced bluestein", &download(&buffer), &expected.concat(), |_| true);
+}
+
+#[test]
+fn forced_bluestein_matches_reference() {
+    each_precision(forced_bluestein::<f32>, forced_bluestein::<f64>);
+}
+
+fn tuned<T: Scalar>(gpu: &Gpu) {
+    let size: &[u64] = &[1000, 6];
+
+    let cpu = reference(size, 1);
+    let input = signal(2 * cpu.elements(), 10);
+    let buffer = gpu.upload::<T>(&input);
+
+    let tuning = TuningOptions {
+        aim_threads: Some(64),
+        coalesced_memory: Some(128),
+        register_boost_non_pow2: true,
+        use_lut: Some(true),
+        ..Default::default()
+    };
+
+    let config = gpu
+        .config(size)
+        .buffer(Buffer::typed(buffer.clone()))
+        .tuning(tuning)
+        .build()
+        .expect("config");
+
+    let mut expected = complex(&input);
+    cpu.fft(&mut expected, false);
+
+    let mut app = App::new(config).expect("app");
+
+    // None of these are VkFFT's defaults in single or double precision, so they came from the
+    // tuning options. Only the lookup tables are also on by default in double precision.
+    let info = app.plan_info();
+    assert_eq!(info.aim_threads, 64);
+    assert_eq!(info.coalesced_memory, 128);
+    assert!(info.register_boost_non_pow2);
+    assert!(info.use_lut);
+
+    app.execute_forward(LaunchParams::builder()).expect("forward");
+
+    compare::<T>("tuned", &download(&buffer), &expected.concat(), |_| true);
+}
+
+#[test]
+fn tuned_matches_reference() {
+    each_precision(tuned::<f32>, tuned::<f64>);
+}
+
+#[cfg(feature = "autotune")]
+#[test]
+fn autotune_round_trips_through_tuning_file() {
+    use vkfft_rs::autotune::{autotune_into, TuningFile};
+
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 256, 11));
+    let builder = gpu.config(&[256]).buffer(Buffer::typed(buffer));
+
+    let path = std::env::temp_dir().join(format!("vkfft-rs-tuning-{}.json", std::process::id()));
+    let mut file = TuningFile::load(&path).expect("load");
+    let entry = autotune_into(&mut file, &builder, 1).expect("autotune");
+    assert_eq!(file.get(&entry.key), Some(&entry));
+    file.save(&path).expect("save");
+
+    let config = builder.tuning_file(&path).build().expect("config");
+    std::fs::remove_file(&path).expect("remove");
+
+    assert_eq!(config.tuning, entry.tuning);
+}
+
+#[cfg(feature = "wisdom")]
+#[test]
+fn autotune_records_the_winner_in_wisdom() {
+    use vkfft_rs::autotune::autotune;
+
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 320, 17));
+    let builder = gpu.config(&[320]).buffer(Buffer::typed(buffer));
+
+    let entry = autotune(&builder, 1).expect("autotune");
+
+    // Without a tuning of its own, the config takes the one the wisdom has for the plan, which is
+    // the winner rather than the last candidate measured.
+    let config = builder.build().expect("config");
+    assert_eq!(config.tuning, entry.tuning);
+    assert!(config.record_wisdom);
+}
+
+#[cfg(feature = "wisdom")]
+#[test]
+fn wisdom_round_trips_through_export() {
+    use vkfft_rs::wisdom::Wisdom;
+
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    /// Restores `save_applications` of the global wisdom, even if the test fails.
+    struct SaveApplications(bool);
+
+    impl Drop for SaveApplications {
+        fn drop(&mut self) {
+            Wisdom::global().save_applications = self.0;
+        }
+    }
+
+    let _restore = SaveApplications(std::mem::replace(&mut Wisdom::global().save_applications, true));
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 128, 13));
+    let config = gpu
+        .config(&[128])
+        .buffer(Buffer::typed(buffer))
+        .record_wisdom()
+        .build()
+        .expect("config");
+    App::new(config).expect("app");
+
+    let json = Wisdom::global().export().expect("export");
+
+    let mut wisdom = Wisdom::new();
+    let report = wisdom.import(&json, gpu.device.physical_device()).expect("import");
+    assert!(report.added >= 1);
+    assert!(report.skipped.is_empty());
+    assert!(wisdom.entries.iter().any(|e| e.application.is_some()));
+}
+
+#[cfg(feature = "wisdom")]
+#[test]
+fn wisdom_import_skips_other_versions() {
+    use vkfft_rs::wisdom::{SkipReason, Wisdom, WisdomEntry};
+
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let buffer = gpu.upload::<f32>(&signal(2 * 96, 19));
+    let config = gpu
+        .config(&[96])
+        .buffer(Buffer::typed(buffer))
+        .record_wisdom()
+        .build()
+        .expect("config");
+    App::new(config).expect("app");
+
+    // Every entry of the global wisdom comes from this device, driver and VkFFT version.
+    let current = Wisdom::global().entries.first().cloned().expect("recorded entry");
+
+    let entry = |name: &str, change: fn(&mut WisdomEntry)| {
+        let mut entry = WisdomEntry {
+            plan: format!("plan of {}", name),
+            application: None,
+            ..current.clone()
+        };
+        change(&mut entry);
+        entry
+    };
+
+    let other_device = entry("other device", |e| e.device = "0000:0000".to_string());
+    let other_driver = entry("other driver", |e| e.driver_version = e.driver_version.wrapping_add(1));
+    let other_version = entry("other version", |e| e.vkfft_version = "0.0.0".to_string());
+
+    let mut exported = Wisdom::new();
+    exported.insert(entry("current", |_| {}));
+    exported.insert(other_device.clone());
+    exported.insert(other_driver.clone());
+    exported.insert(other_version.clone());
+
+    let mut wisdom = Wisdom::new();
+    let report = wisdom
+        .import(&exported.export().expect("export"), gpu.device.physical_device())
+        .expect("import");
+
+    assert_eq!(report.added, 1);
+    assert_eq!(
+        report.skipped,
+        [
+            (other_device, SkipReason::Device),
+            (other_driver, SkipReason::DriverVersion),
+            (other_version, SkipReason::VkfftVersion),
+        ]
+    );
+    assert_eq!(wisdom.entries.len(), 1);
+}
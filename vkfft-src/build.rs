@@ -14,6 +14,8 @@ struct BuildArgs{
     toolchain_file: PathBuf,
     target_os: String,
     android_abi: String,
+    android_platform: Option<String>,
+    ndk_home: PathBuf,
 }
 
 
@@ -26,6 +28,10 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     println!("cargo:rerun-if-changed=wrapper/wrapper.cpp");
     println!("cargo:rerun-if-changed=build.rs");
+    for var in ANDROID_NDK_VARS.iter().chain(ANDROID_PLATFORM_VARS.iter()) {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    println!("cargo:rerun-if-env-changed=CARGO_NDK_SYSROOT_PATH");
 
 
     build_args.out_dir = out_dir;
@@ -54,8 +60,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         build_args.android_abi = android_abi.to_string();
 
-        let ndk_home = PathBuf::from(env::var("ANDROID_NDK_HOME").expect("ANDROID_NDK_HOME not set!"));
-        build_args.toolchain_file = ndk_home.join("build").join("cmake").join("android.toolchain.cmake");
+        build_args.ndk_home = android_ndk_home();
+        build_args.android_platform = android_platform();
+        build_args.toolchain_file = build_args.ndk_home.join("build").join("cmake").join("android.toolchain.cmake");
     }
 
 
@@ -126,7 +133,7 @@ fn build_glslang(build_args: &BuildArgs){
     if build_args.target_os=="android" {
         cmd.arg(format!("-DANDROID_ABI={}", build_args.android_abi));
         cmd.arg("-DANDROID_STL=c++_static");
-        cmd.arg("-DANDROID_PLATFORM=android-24");
+        cmd.arg(format!("-DANDROID_PLATFORM={}", build_args.android_platform.as_deref().unwrap_or("android-24")));
         cmd.arg("-G").arg("Ninja");
         cmd.arg(format!("-DCMAKE_TOOLCHAIN_FILE={}", build_args.toolchain_file.display()));
     }
@@ -152,7 +159,7 @@ fn build_vkfft(build_args: &BuildArgs){
     if build_args.target_os=="android" {
         cmd.arg(format!("-DANDROID_ABI={}", build_args.android_abi));
         cmd.arg("-DANDROID_STL=c++_static");
-        cmd.arg("-DANDROID_PLATFORM=android-26");
+        cmd.arg(format!("-DANDROID_PLATFORM={}", build_args.android_platform.as_deref().unwrap_or("android-26")));
         cmd.arg("-G").arg("Ninja");
         cmd.arg(format!("-DCMAKE_TOOLCHAIN_FILE={}", build_args.toolchain_file.display()));
     }
@@ -167,6 +174,35 @@ fn build_vkfft(build_args: &BuildArgs){
     run(cmd)
 }
 
+/// NDK 根目录的环境变量, 按优先级排列. cargo-ndk 会设置 `ANDROID_NDK_HOME`/`ANDROID_NDK_ROOT`.
+const ANDROID_NDK_VARS: [&str; 3] = ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT", "NDK_HOME"];
+
+/// Android API level 的环境变量, 按优先级排列. `CARGO_NDK_ANDROID_PLATFORM` 由 cargo-ndk 的 `--platform` 设置.
+/// 都没设置时 glslang 用 android-24, wrapper 用 android-26.
+const ANDROID_PLATFORM_VARS: [&str; 2] = ["VKFFT_ANDROID_PLATFORM", "CARGO_NDK_ANDROID_PLATFORM"];
+
+fn android_ndk_home() -> PathBuf {
+    ANDROID_NDK_VARS
+        .iter()
+        .find_map(env::var_os)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| panic!("none of {:?} is set!", ANDROID_NDK_VARS))
+}
+
+/// 接受 `26` 或 `android-26`, 统一成 cmake 需要的 `android-26`.
+fn android_platform() -> Option<String> {
+    let level = ANDROID_PLATFORM_VARS
+        .iter()
+        .find_map(|var| env::var(var).ok())?;
+    let level = level.trim().trim_start_matches("android-");
+
+    if level.parse::<u32>().is_err() {
+        panic!("invalid android platform: {}", level);
+    }
+
+    Some(format!("android-{}", level))
+}
+
 fn run(mut cmd: Command){
     println!("running: {:?}", cmd);
     let status = cmd.status().unwrap();
@@ -201,12 +237,25 @@ fn gen_wrapper<F, const N: usize, const M: usize>(build_args: &BuildArgs, file:
     let mut cfg = bindgen::Builder::default();
 
     if build_args.target_os == "android" {
-        let mut sys_path = PathBuf::from(env::var("ANDROID_NDK_HOME").unwrap());
-        sys_path = sys_path.join("toolchains/llvm/prebuilt");
-        //TODO 只有win
-        sys_path = sys_path.join("windows-x86_64");
-
-        sys_path = sys_path.join("sysroot");
+        // cargo-ndk 直接给出 sysroot, 否则按 host 找 NDK 预编译工具链. NDK 只提供 x86_64 的 host 工具链,
+        // Apple Silicon 上也是 `darwin-x86_64`.
+        let sys_path = match env::var_os("CARGO_NDK_SYSROOT_PATH") {
+            Some(sys_path) => PathBuf::from(sys_path),
+            None => {
+                let host = env::var("HOST").unwrap();
+                let host_tag = if host.contains("windows") {
+                    "windows-x86_64"
+                } else if host.contains("apple") {
+                    "darwin-x86_64"
+                } else if host.contains("linux") {
+                    "linux-x86_64"
+                } else {
+                    panic!("host not support: {}", host);
+                };
+
+                build_args.ndk_home.join("toolchains/llvm/prebuilt").join(host_tag).join("sysroot")
+            }
+        };
 
         cfg = cfg.clang_arg("--sysroot").clang_arg(sys_path.display().to_string());
     }